@@ -5,7 +5,7 @@
   (delay (pods/load-pod "./pod-eigs/target/release/pod-eigs")))
 
 (defn eigenvalues
-  "Compute eigenvalues for a 6x6 matrix.
+  "Compute eigenvalues for a square matrix of any dimension.
 
   The pod declares JSON format, so Babashka handles EDN<->JSON conversion.
   Callers can pass EDN maps directly.
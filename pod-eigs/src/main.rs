@@ -1,10 +1,13 @@
-use nalgebra::{linalg::Schur, SMatrix, SymmetricEigen};
+use nalgebra::{linalg::Schur, DMatrix, DVector, Dyn, SymmetricEigen};
 use num_complex::Complex;
+use rayon::prelude::*;
 use serde_json::Value;
+use std::cell::Cell;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::io::{self, Read, Write};
-use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 enum Bencode {
@@ -21,12 +24,50 @@ enum ParseError {
 }
 
 static PARSE_ERROR_COUNT: AtomicUsize = AtomicUsize::new(0);
+// Process-wide counters reported by the `stats` op.
+static MESSAGE_COUNT: AtomicUsize = AtomicUsize::new(0);
+static INVOKE_COUNT: AtomicUsize = AtomicUsize::new(0);
+static INVOKE_ERROR_COUNT: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BUFFER: AtomicUsize = AtomicUsize::new(0);
+static STARTED: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+static LAST_ERROR: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+fn record_error(msg: &str) {
+    if let Ok(mut last) = LAST_ERROR.lock() {
+        *last = Some(msg.to_string());
+    }
+}
+thread_local! {
+    // Requests that expect an answer vs. terminal responses written on this
+    // thread's message loop, asserted equal so a request is never answered
+    // twice or not at all. An invoke's terminal response is its `done`.
+    static REQUESTS_IN: Cell<usize> = const { Cell::new(0) };
+    static RESPONSES_OUT: Cell<usize> = const { Cell::new(0) };
+}
+
+fn bump(counter: &'static std::thread::LocalKey<Cell<usize>>) {
+    counter.with(|c| c.set(c.get() + 1));
+}
 
 fn parse_int(bytes: &[u8]) -> Result<i64, ParseError> {
     let s = std::str::from_utf8(bytes)
         .map_err(|_| ParseError::Invalid("invalid int utf8".to_string()))?;
+    // Canonical bencode: optional '-', no '+', no leading zeros, no -0.
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseError::Invalid(format!("invalid int {:?}", s)));
+    }
+    if digits.len() > 1 && digits.starts_with('0') {
+        return Err(ParseError::Invalid(format!(
+            "int {:?} has a leading zero",
+            s
+        )));
+    }
+    if s == "-0" {
+        return Err(ParseError::Invalid("int -0 is not canonical".to_string()));
+    }
     s.parse::<i64>()
-        .map_err(|_| ParseError::Invalid("invalid int".to_string()))
+        .map_err(|_| ParseError::Invalid(format!("int {:?} is out of range", s)))
 }
 
 fn parse_len(bytes: &[u8]) -> Result<usize, ParseError> {
@@ -36,10 +77,38 @@ fn parse_len(bytes: &[u8]) -> Result<usize, ParseError> {
         .map_err(|_| ParseError::Invalid("invalid len".to_string()))
 }
 
-fn parse_at(buf: &[u8], mut idx: usize) -> Result<(Bencode, usize), ParseError> {
+/// Deepest list/dict nesting accepted from the host. Pod messages nest a
+/// handful of levels; the bound keeps a stream of `l` bytes from
+/// overflowing the stack.
+const MAX_NESTING_DEPTH: usize = 64;
+
+/// Rejects dicts whose keys are not in strictly ascending byte order, as
+/// canonical bencode requires. Off by default; set from
+/// `POD_EIGS_STRICT_BENCODE` for protocol conformance testing.
+static STRICT_BENCODE: AtomicBool = AtomicBool::new(false);
+
+fn parse_at(buf: &[u8], idx: usize) -> Result<(Bencode, usize), ParseError> {
+    parse_with(buf, idx, STRICT_BENCODE.load(AtomicOrdering::Relaxed))
+}
+
+fn parse_with(buf: &[u8], idx: usize, strict: bool) -> Result<(Bencode, usize), ParseError> {
+    parse_nested(buf, idx, 0, strict)
+}
+
+fn parse_nested(
+    buf: &[u8],
+    mut idx: usize,
+    depth: usize,
+    strict: bool,
+) -> Result<(Bencode, usize), ParseError> {
     if idx >= buf.len() {
         return Err(ParseError::NeedMore);
     }
+    if matches!(buf[idx], b'l' | b'd') && depth >= MAX_NESTING_DEPTH {
+        return Err(ParseError::Invalid(
+            "max nesting depth exceeded".to_string(),
+        ));
+    }
     match buf[idx] {
         b'i' => {
             idx += 1;
@@ -63,7 +132,7 @@ fn parse_at(buf: &[u8], mut idx: usize) -> Result<(Bencode, usize), ParseError>
                 if buf[idx] == b'e' {
                     return Ok((Bencode::List(list), idx + 1));
                 }
-                let (item, next) = parse_at(buf, idx)?;
+                let (item, next) = parse_nested(buf, idx, depth + 1, strict)?;
                 list.push(item);
                 idx = next;
             }
@@ -78,16 +147,20 @@ fn parse_at(buf: &[u8], mut idx: usize) -> Result<(Bencode, usize), ParseError>
                 if buf[idx] == b'e' {
                     return Ok((Bencode::Dict(dict), idx + 1));
                 }
-                let (key, next) = parse_at(buf, idx)?;
+                let (key, next) = parse_nested(buf, idx, depth + 1, strict)?;
                 let key_bytes = match key {
                     Bencode::Bytes(b) => b,
-                    _ => {
-                        return Err(ParseError::Invalid(
-                            "dict key must be bytes".to_string(),
-                        ))
-                    }
+                    _ => return Err(ParseError::Invalid("dict key must be bytes".to_string())),
                 };
-                let (val, next2) = parse_at(buf, next)?;
+                if strict
+                    && dict
+                        .keys()
+                        .next_back()
+                        .is_some_and(|last| key_bytes <= *last)
+                {
+                    return Err(ParseError::Invalid("dict keys not sorted".to_string()));
+                }
+                let (val, next2) = parse_nested(buf, next, depth + 1, strict)?;
                 dict.insert(key_bytes, val);
                 idx = next2;
             }
@@ -155,14 +228,6 @@ fn bencode_str(val: &Bencode) -> Option<String> {
     }
 }
 
-fn bencode_int(val: &Bencode) -> Option<i64> {
-    if let Bencode::Int(i) = val {
-        Some(*i)
-    } else {
-        None
-    }
-}
-
 fn json_number_to_f64(v: &Value) -> Option<f64> {
     match v {
         Value::Number(n) => n.as_f64().or_else(|| n.as_i64().map(|x| x as f64)),
@@ -170,336 +235,7211 @@ fn json_number_to_f64(v: &Value) -> Option<f64> {
     }
 }
 
-fn build_matrix(input: &Value) -> Result<(SMatrix<f64, 6, 6>, bool), String> {
-    let symmetric = input
-        .get("symmetric")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
+/// Parses a decimal string such as `"1.5e-3"` or a `p/q` fraction such as
+/// `"1/3"`.
+fn parse_numeric_string(text: &str) -> Option<f64> {
+    let value = match text.trim().split_once('/') {
+        Some((p, q)) => {
+            let q: f64 = q.trim().parse().ok()?;
+            if q == 0.0 {
+                return None;
+            }
+            p.trim().parse::<f64>().ok()? / q
+        }
+        None => text.trim().parse().ok()?,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Converts one matrix entry at `(row, col)`, accepting numeric strings
+/// only when `allow_strings` is set.
+fn matrix_entry(
+    val: &Value,
+    row: usize,
+    col: usize,
+    what: &str,
+    allow_strings: bool,
+) -> Result<f64, String> {
+    match val {
+        Value::String(text) if allow_strings => parse_numeric_string(text).ok_or_else(|| {
+            format!(
+                "{} entry ({}, {}) is not a number or fraction: {:?}",
+                what, row, col, text
+            )
+        }),
+        _ => json_number_to_f64(val).ok_or_else(|| format!("{} entries must be numbers", what)),
+    }
+}
+
+fn parse_rows(rows: &Value, what: &str) -> Result<DMatrix<f64>, String> {
+    parse_rows_with(rows, what, false)
+}
 
-    if let Some(rows) = input.get("rows") {
-        let rows = rows
+fn parse_rows_with(rows: &Value, what: &str, allow_strings: bool) -> Result<DMatrix<f64>, String> {
+    let rows = rows
+        .as_array()
+        .ok_or_else(|| format!("{} must be a vector of rows", what))?;
+    let ncols = rows
+        .first()
+        .and_then(|r| r.as_array())
+        .map_or(0, |r| r.len());
+    let mut data = Vec::with_capacity(rows.len() * ncols);
+    for (i, row) in rows.iter().enumerate() {
+        let row = row
             .as_array()
-            .ok_or_else(|| "rows must be a vector".to_string())?;
-        if rows.len() != 6 {
-            return Err("rows must have length 6".to_string());
+            .ok_or_else(|| "row must be a vector".to_string())?;
+        if row.len() != ncols {
+            return Err(format!("{} must be rectangular", what));
         }
-        let mut data = [0.0f64; 36];
-        for (i, row) in rows.iter().enumerate() {
-            let row = row
-                .as_array()
-                .ok_or_else(|| "row must be a vector".to_string())?;
-            if row.len() != 6 {
-                return Err("each row must have length 6".to_string());
-            }
-            for (j, val) in row.iter().enumerate() {
-                let num = json_number_to_f64(val)
-                    .ok_or_else(|| "row entries must be numbers".to_string())?;
-                data[i * 6 + j] = num;
-            }
+        for (j, val) in row.iter().enumerate() {
+            data.push(matrix_entry(val, i, j, what, allow_strings)?);
         }
-        Ok((SMatrix::from_row_slice(&data), symmetric))
+    }
+    Ok(DMatrix::from_row_slice(rows.len(), ncols, &data))
+}
+
+fn read_matrix(input: &Value) -> Result<DMatrix<f64>, String> {
+    let allow_strings = input_flag(input, "allow_string_entries");
+    let matrix = if let Some(rows) = input.get("rows") {
+        parse_rows_with(rows, "rows", allow_strings)?
     } else if let Some(data) = input.get("data") {
         let data = data
             .as_array()
             .ok_or_else(|| "data must be a vector".to_string())?;
-        if data.len() != 36 {
-            return Err("data must have length 36".to_string());
+        // n*n entries; the dimension is inferred from the length.
+        let n = (data.len() as f64).sqrt().round() as usize;
+        if n * n != data.len() {
+            return Err(format!(
+                "data length {} is not a perfect square",
+                data.len()
+            ));
         }
-        let mut arr = [0.0f64; 36];
-        for (i, val) in data.iter().enumerate() {
-            let num = json_number_to_f64(val)
-                .ok_or_else(|| "data entries must be numbers".to_string())?;
-            arr[i] = num;
+        let column_major = match input.get("layout") {
+            None => false,
+            Some(v) => match v.as_str() {
+                Some("row") => false,
+                Some("column") => true,
+                _ => return Err("layout must be \"row\" or \"column\"".to_string()),
+            },
+        };
+        let entries = data
+            .iter()
+            .enumerate()
+            .map(|(i, val)| {
+                let (row, col) = if column_major {
+                    (i % n, i / n)
+                } else {
+                    (i / n, i % n)
+                };
+                matrix_entry(val, row, col, "data", allow_strings)
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+        if column_major {
+            DMatrix::from_column_slice(n, n, &entries)
+        } else {
+            DMatrix::from_row_slice(n, n, &entries)
         }
-        Ok((SMatrix::from_row_slice(&arr), symmetric))
     } else {
-        Err("expected :data (len 36) or :rows (6x6)".to_string())
+        return Err("expected :data (n*n row-major) or :rows (n x n)".to_string());
+    };
+    if !matrix.is_square() {
+        return Err(format!(
+            "matrix must be square, got {}x{}",
+            matrix.nrows(),
+            matrix.ncols()
+        ));
+    }
+    if matrix.is_empty() {
+        return Err("matrix must not be empty".to_string());
     }
+    ensure_finite(matrix)
 }
 
-fn check_symmetric(m: &SMatrix<f64, 6, 6>, eps: f64) -> bool {
-    for i in 0..6 {
-        for j in (i + 1)..6 {
-            if (m[(i, j)] - m[(j, i)]).abs() > eps {
-                return false;
+/// NaN or infinite entries make the solvers return garbage instead of
+/// failing, so they are rejected up front.
+fn ensure_finite(matrix: DMatrix<f64>) -> Result<DMatrix<f64>, String> {
+    for i in 0..matrix.nrows() {
+        for j in 0..matrix.ncols() {
+            if !matrix[(i, j)].is_finite() {
+                return Err(format!("matrix contains non-finite entry at ({},{})", i, j));
             }
         }
     }
-    true
+    Ok(matrix)
 }
 
-fn eigenvalues_for(matrix: SMatrix<f64, 6, 6>, symmetric: bool) -> Result<Value, String> {
-    if symmetric {
-        if !check_symmetric(&matrix, 1.0e-9) {
-            return Err("matrix is not symmetric within epsilon".to_string());
-        }
-        let eigen = SymmetricEigen::new(matrix);
-        let mut values: Vec<f64> = eigen.eigenvalues.iter().cloned().collect();
-        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
-        Ok(serde_json::json!({ "eigenvalues": values }))
-    } else {
-        // Bound Schur iterations so pathological matrices cannot run forever.
-        let eps = 1.0e-12_f64;
-        let max_niter = 256_usize;
-        let schur = Schur::try_new(matrix, eps, max_niter)
-            .ok_or_else(|| format!("schur decomposition failed to converge within {} iterations", max_niter))?;
-        let complex_vals = schur.complex_eigenvalues();
-        let mut values: Vec<Complex<f64>> = complex_vals.iter().cloned().collect();
-        values.sort_by(|a, b| {
-            let re = a.re.partial_cmp(&b.re).unwrap_or(Ordering::Equal);
-            if re == Ordering::Equal {
-                a.im.partial_cmp(&b.im).unwrap_or(Ordering::Equal)
+/// Mirrors one triangle onto the other so that only it is authoritative.
+fn symmetrize_from_triangle(matrix: &mut DMatrix<f64>, upper: bool) {
+    let n = matrix.nrows();
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if upper {
+                matrix[(j, i)] = matrix[(i, j)];
             } else {
-                re
+                matrix[(i, j)] = matrix[(j, i)];
             }
-        });
-        let pairs: Vec<[f64; 2]> = values.iter().map(|c| [c.re, c.im]).collect();
-        Ok(serde_json::json!({ "eigenvalues": pairs }))
+        }
     }
 }
 
-fn response_map(id: Option<Bencode>, pairs: Vec<(&str, Bencode)>) -> Bencode {
-    let mut dict = BTreeMap::new();
-    if let Some(id) = id {
-        dict.insert(b"id".to_vec(), id);
+fn build_matrix(input: &Value) -> Result<(DMatrix<f64>, bool), String> {
+    let symmetric = input
+        .get("symmetric")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let mut matrix = read_matrix(input)?;
+
+    match input.get("read_triangle").map(|v| v.as_str()) {
+        None | Some(Some("both")) => {}
+        Some(Some(tri @ ("upper" | "lower"))) => {
+            if !symmetric {
+                return Err("read_triangle requires :symmetric true".to_string());
+            }
+            symmetrize_from_triangle(&mut matrix, tri == "upper");
+        }
+        Some(_) => return Err("read_triangle must be \"upper\", \"lower\" or \"both\"".to_string()),
     }
-    for (k, v) in pairs {
-        dict.insert(k.as_bytes().to_vec(), v);
+    Ok((matrix, symmetric))
+}
+
+/// Reads a matrix operand given as a vector of rows under `key`.
+fn matrix_arg(input: &Value, key: &str) -> Result<DMatrix<f64>, String> {
+    let rows = input.get(key).ok_or_else(|| format!("missing :{}", key))?;
+    parse_rows_with(rows, key, input_flag(input, "allow_string_entries")).and_then(ensure_finite)
+}
+
+/// Reader for vars that work on any `m×n` matrix: `:rows` may be
+/// rectangular, while `:data` is still an `n*n` square.
+fn read_rect_matrix(input: &Value) -> Result<DMatrix<f64>, String> {
+    if input.get("rows").is_none() {
+        return read_matrix(input);
     }
-    Bencode::Dict(dict)
+    let matrix = matrix_arg(input, "rows")?;
+    if matrix.is_empty() {
+        return Err("matrix must not be empty".to_string());
+    }
+    Ok(matrix)
 }
 
-fn handle_describe(id: Option<Bencode>, stdout: &mut dyn Write) -> io::Result<()> {
-    let var = Bencode::Dict(BTreeMap::from([
-        (b"name".to_vec(), Bencode::Bytes(b"eigenvalues".to_vec())),
-        (
-            b"doc".to_vec(),
-            Bencode::Bytes(b"Compute eigenvalues for a 6x6 matrix.".to_vec()),
-        ),
-        (
-            b"arglists".to_vec(),
-            Bencode::Bytes(b"([m])".to_vec()),
-        ),
-    ]));
+fn number_arg(input: &Value, key: &str) -> Result<f64, String> {
+    let v = input.get(key).ok_or_else(|| format!("missing :{}", key))?;
+    let num = json_number_to_f64(v).ok_or_else(|| format!("{} must be a number", key))?;
+    if !num.is_finite() {
+        return Err(format!("{} must be finite", key));
+    }
+    Ok(num)
+}
 
-    let ns = Bencode::Dict(BTreeMap::from([
-        (b"name".to_vec(), Bencode::Bytes(b"pod.eigs".to_vec())),
-        (b"vars".to_vec(), Bencode::List(vec![var])),
-    ]));
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Layout {
+    #[default]
+    Row,
+    Column,
+}
 
-    let resp = response_map(
-        id,
-        vec![
-            ("op", Bencode::Bytes(b"describe".to_vec())),
-            ("format", Bencode::Bytes(b"json".to_vec())),
-            ("namespaces", Bencode::List(vec![ns])),
-        ],
-    );
+fn output_layout(input: &Value) -> Result<Layout, String> {
+    match input.get("output_layout").map(|v| v.as_str()) {
+        None | Some(Some("row")) => Ok(Layout::Row),
+        Some(Some("column")) => Ok(Layout::Column),
+        Some(_) => Err("output_layout must be \"row\" or \"column\"".to_string()),
+    }
+}
 
-    let encoded = encode_bencode(&resp);
-    stdout.write_all(&encoded)?;
-    stdout.flush()?;
-    Ok(())
+/// Serializes a matrix as nested rows, or nested columns for `Layout::Column`.
+fn matrix_value(m: &DMatrix<f64>, layout: Layout) -> Value {
+    let nested: Vec<Vec<f64>> = match layout {
+        Layout::Row => m.row_iter().map(|r| r.iter().cloned().collect()).collect(),
+        Layout::Column => m
+            .column_iter()
+            .map(|c| c.iter().cloned().collect())
+            .collect(),
+    };
+    serde_json::json!(nested)
 }
 
-fn write_error(id: Option<Bencode>, msg: &str, stdout: &mut dyn Write) -> io::Result<()> {
-    let resp = response_map(
-        id,
-        vec![
-            ("op", Bencode::Bytes(b"invoke".to_vec())),
-            ("ex-message", Bencode::Bytes(msg.as_bytes().to_vec())),
-            ("ex-type", Bencode::Bytes(b"Exception".to_vec())),
-        ],
-    );
-    let encoded = encode_bencode(&resp);
-    stdout.write_all(&encoded)?;
-    stdout.flush()?;
+fn check_symmetric(m: &DMatrix<f64>, eps: f64) -> bool {
+    ensure_symmetric(m, eps).is_ok()
+}
+
+/// Errors on the first pair `(i, j)` with `|a_ij − a_ji| > eps`, naming
+/// both indices and the delta.
+fn ensure_symmetric(m: &DMatrix<f64>, eps: f64) -> Result<(), String> {
+    let n = m.nrows();
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let delta = (m[(i, j)] - m[(j, i)]).abs();
+            if delta > eps {
+                return Err(format!(
+                    "matrix is not symmetric within {:e}: |a[{}][{}] - a[{}][{}]| = {:e}",
+                    eps, i, j, j, i, delta
+                ));
+            }
+        }
+    }
     Ok(())
 }
 
-fn handle_invoke(dict: &BTreeMap<Vec<u8>, Bencode>, stdout: &mut dyn Write) -> io::Result<()> {
-    let id = dict_get(dict, "id").cloned();
-    let var = dict_get(dict, "var").and_then(bencode_str);
+/// Symmetry tolerance for the `:symmetric` path, `:sym_tol` (default `1e-9`).
+fn sym_tol(input: &Value) -> Result<f64, String> {
+    tolerance_arg(input, "sym_tol", 1.0e-9)
+}
 
-    let var = match var {
-        Some(v) => v,
-        None => return write_error(id, "missing var", stdout),
-    };
+/// Symmetric eigenpairs sorted ascending; eigenvectors are the matching columns.
+fn sorted_symmetric_eigen(matrix: DMatrix<f64>) -> (Vec<f64>, DMatrix<f64>) {
+    let eigen = SymmetricEigen::new(matrix);
+    let mut order: Vec<usize> = (0..eigen.eigenvalues.len()).collect();
+    order.sort_by(|&a, &b| {
+        eigen.eigenvalues[a]
+            .partial_cmp(&eigen.eigenvalues[b])
+            .unwrap_or(Ordering::Equal)
+    });
+    let values = order.iter().map(|&i| eigen.eigenvalues[i]).collect();
+    let columns: Vec<_> = order
+        .iter()
+        .map(|&i| eigen.eigenvectors.column(i))
+        .collect();
+    (values, DMatrix::from_columns(&columns))
+}
 
-    if var != "pod.eigs/eigenvalues" {
-        return write_error(id, "unknown var", stdout);
-    }
+/// Per-eigenvalue absolute error bounds for a symmetric matrix. For normal
+/// matrices Bauer–Fike reduces to `|λ - λ_true| <= ||A v - λ v||` for unit
+/// `v`; the extra `n * eps * ||A||_F` term covers rounding in the residual.
+fn symmetric_error_bounds(
+    matrix: &DMatrix<f64>,
+    values: &[f64],
+    vectors: &DMatrix<f64>,
+) -> Vec<f64> {
+    let slack = matrix.nrows() as f64 * f64::EPSILON * matrix.norm();
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &lambda)| {
+            let v = vectors.column(i);
+            (matrix * v - v * lambda).norm() / v.norm() + slack
+        })
+        .collect()
+}
 
-    let args = dict_get(dict, "args");
-    let arg_bytes = match args {
-        Some(Bencode::List(items)) if !items.is_empty() => match &items[0] {
-            Bencode::Bytes(b) => Some(b.clone()),
-            _ => None,
-        },
-        Some(Bencode::Bytes(b)) => Some(b.clone()),
-        _ => None,
-    };
+/// Inverse participation ratio `Σ v_i^4` of each unit-normalized column:
+/// about `1/n` for a delocalized vector and 1 for a basis vector.
+fn participation_ratios(vectors: &DMatrix<f64>) -> Vec<f64> {
+    vectors
+        .column_iter()
+        .map(|v| {
+            let norm2 = v.norm_squared();
+            v.iter().map(|x| x.powi(4)).sum::<f64>() / (norm2 * norm2)
+        })
+        .collect()
+}
 
-    let arg_bytes = match arg_bytes {
-        Some(b) => b,
-        None => return write_error(id, "missing args", stdout),
-    };
+fn symmetric_eigenvalues(matrix: DMatrix<f64>) -> Vec<f64> {
+    let eigen = SymmetricEigen::new(matrix);
+    let mut values: Vec<f64> = eigen.eigenvalues.iter().cloned().collect();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    values
+}
 
-    let json_input: Value = match serde_json::from_slice(&arg_bytes) {
-        Ok(v) => v,
-        Err(_) => return write_error(id, "invalid json input", stdout),
-    };
+fn cmp_complex(a: &Complex<f64>, b: &Complex<f64>) -> Ordering {
+    let re = a.re.partial_cmp(&b.re).unwrap_or(Ordering::Equal);
+    if re == Ordering::Equal {
+        a.im.partial_cmp(&b.im).unwrap_or(Ordering::Equal)
+    } else {
+        re
+    }
+}
 
-    let json_input = match json_input {
-        Value::Array(mut items) if items.len() == 1 => items.remove(0),
-        Value::Array(_) => return write_error(id, "expected single arg map", stdout),
-        other => other,
-    };
+const SCHUR_EPS: f64 = 1.0e-12;
+// Bound Schur iterations so pathological matrices cannot run forever.
+const SCHUR_MAX_ITER: usize = 256;
 
-    let (matrix, symmetric) = match build_matrix(&json_input) {
-        Ok(v) => v,
-        Err(e) => return write_error(id, &e, stdout),
-    };
+fn general_eigenvalues(matrix: DMatrix<f64>) -> Result<Vec<Complex<f64>>, String> {
+    schur_eigenvalues(matrix, SCHUR_EPS, SCHUR_MAX_ITER)
+}
 
-    let output = match eigenvalues_for(matrix, symmetric) {
-        Ok(v) => v,
-        Err(e) => return write_error(id, &e, stdout),
-    };
+/// Sorted eigenvalues and the real Schur factor `T`. nalgebra rescales by
+/// the largest entry before iterating, so an all-zero matrix of dimension 3
+/// or more never converges (2x2 and smaller are solved in closed form); it
+/// is already in Schur form with every eigenvalue zero.
+fn schur_decomposition(
+    matrix: DMatrix<f64>,
+    eps: f64,
+    max_niter: usize,
+) -> Result<(Vec<Complex<f64>>, DMatrix<f64>), String> {
+    if matrix.iter().all(|x| *x == 0.0) {
+        return Ok((vec![Complex::new(0.0, 0.0); matrix.nrows()], matrix));
+    }
+    let schur = Schur::try_new(matrix, eps, max_niter).ok_or_else(|| {
+        format!(
+            "schur decomposition failed to converge within {} iterations",
+            max_niter
+        )
+    })?;
+    let values = sorted_schur_eigenvalues(&schur);
+    Ok((values, schur.unpack().1))
+}
 
-    let value = match serde_json::to_string(&output) {
-        Ok(s) => s,
-        Err(_) => return write_error(id, "failed to serialize output", stdout),
-    };
+/// Smallest iteration budget under which Schur converges. nalgebra does not
+/// report the count, but a run either finishes within its budget or fails,
+/// so bisecting over budgets recovers it (a budget of 0 means unbounded,
+/// hence the floor of 1).
+fn schur_min_iter(matrix: &DMatrix<f64>, eps: f64, max_niter: usize) -> usize {
+    if matrix.iter().all(|x| *x == 0.0) {
+        return 1;
+    }
+    let (mut lo, mut hi) = (1, max_niter);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if Schur::try_new(matrix.clone(), eps, mid).is_some() {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    hi
+}
 
-    let resp = response_map(
-        id,
-        vec![
-            ("op", Bencode::Bytes(b"invoke".to_vec())),
-            ("value", Bencode::Bytes(value.as_bytes().to_vec())),
-        ],
-    );
-    let encoded = encode_bencode(&resp);
-    stdout.write_all(&encoded)?;
-    stdout.flush()?;
-    Ok(())
+fn schur_eigenvalues(
+    matrix: DMatrix<f64>,
+    eps: f64,
+    max_niter: usize,
+) -> Result<Vec<Complex<f64>>, String> {
+    schur_decomposition(matrix, eps, max_niter).map(|(values, _)| values)
 }
 
-fn handle_message(msg: Bencode, stdout: &mut dyn Write) -> io::Result<()> {
-    let dict = match msg {
-        Bencode::Dict(d) => d,
-        _ => return Ok(()),
-    };
-    let op = dict_get(&dict, "op").and_then(bencode_str).unwrap_or_default();
-    let id = dict_get(&dict, "id").cloned();
+fn sorted_schur_eigenvalues(schur: &Schur<f64, Dyn>) -> Vec<Complex<f64>> {
+    let complex_vals = schur.complex_eigenvalues();
+    let mut values: Vec<Complex<f64>> = complex_vals.iter().cloned().collect();
+    values.sort_by(cmp_complex);
+    values
+}
 
-    match op.as_str() {
-        "describe" => handle_describe(id, stdout),
-        "invoke" => handle_invoke(&dict, stdout),
-        "shutdown" => Ok(()),
-        _ => Ok(()),
+/// Lists the diagonal blocks of a real quasi-triangular Schur factor: a
+/// non-zero subdiagonal entry opens a 2x2 block holding a complex pair.
+fn schur_blocks(t: &DMatrix<f64>) -> Vec<Value> {
+    let n = t.nrows();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < n {
+        if i + 1 < n && t[(i + 1, i)] != 0.0 {
+            let half_trace = (t[(i, i)] + t[(i + 1, i + 1)]) / 2.0;
+            let det = t[(i, i)] * t[(i + 1, i + 1)] - t[(i, i + 1)] * t[(i + 1, i)];
+            let im = (det - half_trace * half_trace).max(0.0).sqrt();
+            blocks.push(serde_json::json!({
+                "start": i,
+                "size": 2,
+                "kind": "complex_pair",
+                "eigenvalues": [[half_trace, im], [half_trace, -im]],
+            }));
+            i += 2;
+        } else {
+            blocks.push(serde_json::json!({
+                "start": i,
+                "size": 1,
+                "kind": "real",
+                "eigenvalues": [[t[(i, i)], 0.0]],
+            }));
+            i += 1;
+        }
     }
+    blocks
 }
 
-fn main() -> io::Result<()> {
-    let mut stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut buffer: Vec<u8> = Vec::new();
-    let mut chunk = [0u8; 4096];
+fn input_flag(input: &Value, key: &str) -> bool {
+    input.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
 
-    loop {
-        let n = stdin.read(&mut chunk)?;
-        if n == 0 {
-            break;
+fn tolerance_arg(input: &Value, key: &str, default: f64) -> Result<f64, String> {
+    match input.get(key) {
+        None => Ok(default),
+        Some(v) => match json_number_to_f64(v) {
+            Some(t) if t.is_finite() && t >= 0.0 => Ok(t),
+            _ => Err(format!("{} must be a finite non-negative number", key)),
+        },
+    }
+}
+
+/// Eigenvector condition numbers above this flag the eigenvalues as unreliable.
+const DEFAULT_COND_THRESHOLD: f64 = 1.0e8;
+
+#[derive(Debug, Clone, Default)]
+struct EigOptions {
+    symmetric: bool,
+    error_bounds: bool,
+    participation_ratios: bool,
+    meta: bool,
+    relative_eps: bool,
+    balance: bool,
+    group_by_sign: bool,
+    zero_tol: f64,
+    schur: bool,
+    schur_blocks: bool,
+    layout: Layout,
+    check_conditioning: bool,
+    cond_threshold: f64,
+    /// Overrides `SCHUR_EPS` / `SCHUR_MAX_ITER` for the general path.
+    epsilon: Option<f64>,
+    max_iter: Option<usize>,
+    multiplicities: bool,
+    condition: bool,
+    collapse_real: bool,
+    diagnostics: bool,
+    sym_tol: f64,
+}
+
+impl EigOptions {
+    fn from_input(input: &Value) -> Result<Self, String> {
+        let opts = EigOptions {
+            symmetric: input_flag(input, "symmetric"),
+            error_bounds: input_flag(input, "with_error_bounds"),
+            participation_ratios: input_flag(input, "with_ipr"),
+            meta: input_flag(input, "with_meta"),
+            relative_eps: input_flag(input, "relative_eps"),
+            balance: input_flag(input, "balance"),
+            group_by_sign: input_flag(input, "group_by_sign"),
+            zero_tol: tolerance_arg(input, "zero_tol", 1.0e-9)?,
+            schur: input_flag(input, "with_schur"),
+            schur_blocks: input_flag(input, "schur_blocks"),
+            layout: output_layout(input)?,
+            check_conditioning: input_flag(input, "check_conditioning"),
+            cond_threshold: tolerance_arg(input, "cond_threshold", DEFAULT_COND_THRESHOLD)?,
+            epsilon: match input.get("epsilon") {
+                None => None,
+                Some(v) => match json_number_to_f64(v) {
+                    Some(e) if e.is_finite() && e > 0.0 => Some(e),
+                    _ => return Err("epsilon must be a finite positive number".to_string()),
+                },
+            },
+            max_iter: match input.get("max_iter") {
+                None => None,
+                Some(v) => match v.as_u64() {
+                    Some(k) if k > 0 => Some(k as usize),
+                    _ => return Err("max_iter must be a positive integer".to_string()),
+                },
+            },
+            multiplicities: input_flag(input, "multiplicities"),
+            condition: input_flag(input, "condition"),
+            collapse_real: input_flag(input, "collapse_real"),
+            diagnostics: input_flag(input, "diagnostics"),
+            sym_tol: sym_tol(input)?,
+        };
+        if opts.error_bounds && !opts.symmetric {
+            return Err("with_error_bounds requires :symmetric true".to_string());
         }
-        buffer.extend_from_slice(&chunk[..n]);
+        if opts.participation_ratios && !opts.symmetric {
+            return Err("with_ipr requires :symmetric true".to_string());
+        }
+        if opts.group_by_sign && !opts.symmetric {
+            return Err("group_by_sign requires :symmetric true".to_string());
+        }
+        if opts.schur && opts.symmetric {
+            return Err("with_schur requires the general path (omit :symmetric)".to_string());
+        }
+        if opts.check_conditioning && opts.symmetric {
+            return Err(
+                "check_conditioning requires the general path (omit :symmetric)".to_string(),
+            );
+        }
+        if opts.diagnostics && opts.symmetric {
+            return Err("diagnostics requires the general path (omit :symmetric)".to_string());
+        }
+        if opts.schur_blocks && !opts.schur {
+            return Err("schur_blocks requires :with_schur true".to_string());
+        }
+        if opts.multiplicities
+            && (opts.error_bounds || opts.participation_ratios || opts.group_by_sign)
+        {
+            return Err(
+                "multiplicities cannot be combined with with_error_bounds, with_ipr or group_by_sign".to_string(),
+            );
+        }
+        Ok(opts)
+    }
 
-        loop {
-            match parse_at(&buffer, 0) {
-                Ok((msg, used)) => {
-                    buffer.drain(0..used);
-                    if let Bencode::Dict(ref dict) = msg {
-                        if let Some(Bencode::Bytes(op)) = dict_get(dict, "op") {
-                            if op == b"shutdown" {
-                                return Ok(());
-                            }
-                        }
-                    }
-                    handle_message(msg, &mut stdout)?;
-                }
-                Err(ParseError::NeedMore) => break,
-                Err(ParseError::Invalid(msg)) => {
-                    // Recover by dropping one byte and retrying parse. Clearing the
-                    // whole buffer can desynchronize request/response matching and
-                    // leave the host waiting forever.
-                    let n = PARSE_ERROR_COUNT.fetch_add(1, AtomicOrdering::Relaxed) + 1;
-                    eprintln!(
-                        "pod-eigs parse error #{}: {} (buffer-len={})",
-                        n,
-                        msg,
-                        buffer.len()
-                    );
-                    if !buffer.is_empty() {
-                        buffer.drain(0..1);
-                        continue;
-                    }
-                    break;
+    /// Schur convergence tolerance, scaled by `||A||_F` when `relative_eps`
+    /// is set. Schur already deflates relative to neighbouring diagonal
+    /// entries, so scaling mostly loosens the threshold for large-norm input.
+    fn schur_eps(&self, matrix: &DMatrix<f64>) -> f64 {
+        let eps = self.epsilon.unwrap_or(SCHUR_EPS);
+        let norm = matrix.norm();
+        if self.relative_eps && norm > 0.0 {
+            eps * norm
+        } else {
+            eps
+        }
+    }
+
+    fn schur_max_iter(&self) -> usize {
+        self.max_iter.unwrap_or(SCHUR_MAX_ITER)
+    }
+
+    /// Clustering tolerance for `multiplicities`: `:epsilon` when given,
+    /// else `1e-9`, scaled by the largest eigenvalue magnitude (at least 1).
+    fn cluster_tol(&self, max_abs: f64) -> f64 {
+        self.epsilon.unwrap_or(1.0e-9) * max_abs.max(1.0)
+    }
+}
+
+/// Partitions ascending eigenvalues into negative, zero (`|λ| <= tol`) and
+/// positive groups; concatenated they keep the flat order.
+fn group_by_sign(values: &[f64], tol: f64) -> Value {
+    let negative: Vec<f64> = values.iter().cloned().filter(|&l| l < -tol).collect();
+    let zero: Vec<f64> = values.iter().cloned().filter(|l| l.abs() <= tol).collect();
+    let positive: Vec<f64> = values.iter().cloned().filter(|&l| l > tol).collect();
+    serde_json::json!({ "negative": negative, "zero": zero, "positive": positive })
+}
+
+/// Parlett–Reinsch balancing with radix-2 scalings: returns `B = D^-1 A D`
+/// and the diagonal of `D`. `B` has the same eigenvalues but row and column
+/// norms are equalized, which improves the accuracy of the Schur path.
+fn balance_matrix(matrix: &DMatrix<f64>) -> (DMatrix<f64>, Vec<f64>) {
+    let n = matrix.nrows();
+    let mut b = matrix.clone();
+    let mut d = vec![1.0f64; n];
+    let mut converged = false;
+    while !converged {
+        converged = true;
+        for i in 0..n {
+            let mut c: f64 = (0..n).filter(|&j| j != i).map(|j| b[(j, i)].abs()).sum();
+            let r: f64 = (0..n).filter(|&j| j != i).map(|j| b[(i, j)].abs()).sum();
+            if c == 0.0 || r == 0.0 {
+                continue;
+            }
+            let s = c + r;
+            let mut f = 1.0;
+            while c < r / 2.0 {
+                f *= 2.0;
+                c *= 4.0;
+            }
+            while c >= r * 2.0 {
+                f /= 2.0;
+                c /= 4.0;
+            }
+            if (c + r) / f < 0.95 * s {
+                converged = false;
+                d[i] *= f;
+                for j in 0..n {
+                    b[(j, i)] *= f;
+                    b[(i, j)] /= f;
                 }
             }
         }
     }
+    (b, d)
+}
 
-    Ok(())
+fn complex_pairs(values: &[Complex<f64>]) -> Vec<[f64; 2]> {
+    values.iter().map(|c| [c.re, c.im]).collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Groups sorted complex values whose real and imaginary parts are both
+/// within `tol` of a cluster's first member. Conjugates differ by `2|im|`
+/// in the imaginary part, so a genuine pair is never merged.
+fn cluster_complex(values: &[Complex<f64>], tol: f64) -> Vec<Vec<Complex<f64>>> {
+    let mut clusters: Vec<Vec<Complex<f64>>> = Vec::new();
+    for &v in values {
+        let home = clusters
+            .iter_mut()
+            .find(|c| (c[0].re - v.re).abs() <= tol && (c[0].im - v.im).abs() <= tol);
+        match home {
+            Some(cluster) => cluster.push(v),
+            None => clusters.push(vec![v]),
+        }
+    }
+    clusters
+}
 
-    fn close_enough(a: f64, b: f64) -> bool {
-        (a - b).abs() < 1.0e-6
+/// 2-norm condition number `σ_max / σ_min`. JSON has no infinity, so a
+/// singular matrix reports the string `"inf"`.
+fn condition_number(matrix: &DMatrix<f64>) -> Value {
+    let sv = matrix.singular_values();
+    let max = sv.iter().cloned().fold(0.0, f64::max);
+    let min = sv.iter().cloned().fold(f64::INFINITY, f64::min);
+    let cond = max / min;
+    if min == 0.0 || !cond.is_finite() {
+        Value::String("inf".to_string())
+    } else {
+        serde_json::json!(cond)
     }
+}
 
-    #[test]
-    fn identity_matrix() {
-        let mut data = [0.0f64; 36];
-        for i in 0..6 {
-            data[i * 6 + i] = 1.0;
+fn eigenvalues_for(matrix: DMatrix<f64>, opts: &EigOptions) -> Result<Value, String> {
+    let condition = opts.condition.then(|| condition_number(&matrix));
+    let mut out = spectrum_for(matrix, opts)?;
+    if let Some(cond) = condition {
+        out["condition_number"] = cond;
+    }
+    Ok(out)
+}
+
+fn spectrum_for(matrix: DMatrix<f64>, opts: &EigOptions) -> Result<Value, String> {
+    if opts.symmetric {
+        ensure_symmetric(&matrix, opts.sym_tol)?;
+        let mut out = if opts.error_bounds || opts.participation_ratios {
+            let (values, vectors) = sorted_symmetric_eigen(matrix.clone());
+            let mut out = serde_json::json!({ "eigenvalues": values });
+            if opts.error_bounds {
+                out["error_bounds"] =
+                    serde_json::json!(symmetric_error_bounds(&matrix, &values, &vectors));
+            }
+            if opts.participation_ratios {
+                out["ipr"] = serde_json::json!(participation_ratios(&vectors));
+            }
+            out
+        } else {
+            serde_json::json!({ "eigenvalues": symmetric_eigenvalues(matrix) })
+        };
+        if opts.group_by_sign {
+            let values: Vec<f64> =
+                serde_json::from_value(out["eigenvalues"].take()).map_err(|e| e.to_string())?;
+            out["eigenvalues"] = group_by_sign(&values, opts.zero_tol);
         }
-        let matrix = SMatrix::from_row_slice(&data);
-        let out = eigenvalues_for(matrix, true).unwrap();
-        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
-        assert_eq!(vals.len(), 6);
-        for v in vals {
-            assert!(close_enough(v.as_f64().unwrap(), 1.0));
+        if opts.multiplicities {
+            let values: Vec<f64> =
+                serde_json::from_value(out["eigenvalues"].take()).map_err(|e| e.to_string())?;
+            let max_abs = values.iter().fold(0.0f64, |m, l| m.max(l.abs()));
+            let clusters = cluster_sorted(&values, opts.cluster_tol(max_abs));
+            let means: Vec<f64> = clusters
+                .iter()
+                .map(|c| c.iter().sum::<f64>() / c.len() as f64)
+                .collect();
+            out["eigenvalues"] = serde_json::json!(means);
+            out["multiplicities"] =
+                serde_json::json!(clusters.iter().map(|c| c.len()).collect::<Vec<_>>());
         }
+        Ok(out)
+    } else {
+        let matrix = if opts.balance {
+            balance_matrix(&matrix).0
+        } else {
+            matrix
+        };
+        let eps = opts.schur_eps(&matrix);
+        let (values, t) = schur_decomposition(matrix.clone(), eps, opts.schur_max_iter())?;
+        let mut out = serde_json::json!({ "eigenvalues": complex_pairs(&values) });
+        if opts.multiplicities {
+            let max_abs = values.iter().fold(0.0f64, |m, l| m.max(l.norm()));
+            let clusters = cluster_complex(&values, opts.cluster_tol(max_abs));
+            let means: Vec<Complex<f64>> = clusters
+                .iter()
+                .map(|c| c.iter().sum::<Complex<f64>>() / c.len() as f64)
+                .collect();
+            out["eigenvalues"] = serde_json::json!(complex_pairs(&means));
+            out["multiplicities"] =
+                serde_json::json!(clusters.iter().map(|c| c.len()).collect::<Vec<_>>());
+        }
+        if opts.collapse_real {
+            let pairs: Vec<[f64; 2]> =
+                serde_json::from_value(out["eigenvalues"].clone()).map_err(|e| e.to_string())?;
+            let max_abs = values.iter().fold(0.0f64, |m, l| m.max(l.norm()));
+            let tol = opts.cluster_tol(max_abs);
+            if pairs.iter().all(|[_, im]| im.abs() <= tol) {
+                out["eigenvalues"] =
+                    serde_json::json!(pairs.iter().map(|[re, _]| *re).collect::<Vec<f64>>());
+            }
+        }
+        if opts.check_conditioning {
+            let cond = eigenvector_condition(&matrix, &values);
+            out["eigenvector_condition"] = serde_json::json!(cond);
+            if cond > opts.cond_threshold {
+                out["warning"] = serde_json::json!(format!(
+                    "eigenvector matrix condition number {:e} exceeds {:e}; the matrix is close to defective and its eigenvalues may be unreliable",
+                    cond, opts.cond_threshold
+                ));
+            }
+        }
+        if opts.diagnostics {
+            out["diagnostics"] = serde_json::json!({
+                "converged": true,
+                "max_iter": opts.schur_max_iter(),
+                "epsilon": eps,
+                "min_iter": schur_min_iter(&matrix, eps, opts.schur_max_iter()),
+            });
+        }
+        if opts.schur {
+            out["schur"] = matrix_value(&t, opts.layout);
+            if opts.schur_blocks {
+                out["schur_blocks"] = serde_json::json!(schur_blocks(&t));
+            }
+        }
+        Ok(out)
     }
+}
 
-    #[test]
-    fn zero_matrix() {
-        let data = [0.0f64; 36];
-        let matrix = SMatrix::from_row_slice(&data);
-        let out = eigenvalues_for(matrix, true).unwrap();
-        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
-        assert_eq!(vals.len(), 6);
-        for v in vals {
+fn var_eigenvalues(input: &Value) -> Result<Value, String> {
+    let started = Instant::now();
+    let (matrix, _) = build_matrix(input)?;
+    let opts = EigOptions::from_input(input)?;
+    let dim = matrix.nrows();
+    // Report the tolerance of the matrix actually decomposed, which is the
+    // balanced one under `:balance`.
+    let eps = if opts.balance {
+        opts.schur_eps(&balance_matrix(&matrix).0)
+    } else {
+        opts.schur_eps(&matrix)
+    };
+    let mut out = eigenvalues_for(matrix, &opts)?;
+    if opts.meta {
+        out["meta"] = serde_json::json!({
+            "elapsed_us": started.elapsed().as_micros() as u64,
+            "path": if opts.symmetric { "symmetric" } else { "general" },
+            "dim": dim,
+        });
+        if !opts.symmetric {
+            out["meta"]["epsilon"] = serde_json::json!(eps);
+        }
+    }
+    Ok(out)
+}
+
+fn direct_sum(a: &DMatrix<f64>, b: &DMatrix<f64>) -> DMatrix<f64> {
+    let mut out = DMatrix::zeros(a.nrows() + b.nrows(), a.ncols() + b.ncols());
+    out.view_mut((0, 0), a.shape()).copy_from(a);
+    out.view_mut(a.shape(), b.shape()).copy_from(b);
+    out
+}
+
+fn var_dsum(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    let layout = output_layout(input)?;
+    Ok(serde_json::json!({ "dsum": matrix_value(&direct_sum(&a, &b), layout) }))
+}
+
+fn mean_matrix(matrices: &[DMatrix<f64>]) -> Result<DMatrix<f64>, String> {
+    let first = matrices
+        .first()
+        .ok_or_else(|| "matrices must not be empty".to_string())?;
+    let mut sum = DMatrix::zeros(first.nrows(), first.ncols());
+    for (i, m) in matrices.iter().enumerate() {
+        if m.shape() != first.shape() {
+            return Err(format!(
+                "matrices must share dimensions: matrix {} is {}x{}, expected {}x{}",
+                i,
+                m.nrows(),
+                m.ncols(),
+                first.nrows(),
+                first.ncols()
+            ));
+        }
+        sum += m;
+    }
+    Ok(sum / matrices.len() as f64)
+}
+
+fn var_mean(input: &Value) -> Result<Value, String> {
+    let matrices = input
+        .get("matrices")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "matrices must be a vector of matrices".to_string())?
+        .iter()
+        .map(|m| parse_rows(m, "matrices"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let layout = output_layout(input)?;
+    let mean = mean_matrix(&matrices)?;
+    let mut out = serde_json::json!({ "mean": matrix_value(&mean, layout) });
+    if input_flag(input, "with_eigenvalues") {
+        if !mean.is_square() {
+            return Err("eigenvalues require square matrices".to_string());
+        }
+        let eig = eigenvalues_for(mean, &EigOptions::from_input(input)?)?;
+        out["eigenvalues"] = eig["eigenvalues"].clone();
+    }
+    Ok(out)
+}
+
+fn var_density_eigs(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let tol = 1.0e-9;
+    if !check_symmetric(&matrix, sym_tol(input)?) {
+        return Err("density matrix must be Hermitian (symmetric)".to_string());
+    }
+    let trace = matrix.trace();
+    if trace <= 0.0 {
+        return Err(format!(
+            "density matrix must have positive trace, got {}",
+            trace
+        ));
+    }
+    let mut warnings = Vec::new();
+    if (trace - 1.0).abs() > tol {
+        warnings.push(format!("trace was {}; normalized to 1", trace));
+    }
+    let probabilities = symmetric_eigenvalues(matrix / trace);
+    if probabilities.iter().any(|&p| p < -tol) {
+        warnings.push("matrix has negative eigenvalues; not positive semidefinite".to_string());
+    }
+    let entropy: f64 = probabilities
+        .iter()
+        .filter(|&&p| p > 0.0)
+        .map(|&p| -p * p.ln())
+        .sum();
+    let mut out = serde_json::json!({
+        "probabilities": probabilities,
+        "entropy": entropy,
+        "trace": trace,
+    });
+    if !warnings.is_empty() {
+        out["warning"] = Value::String(warnings.join("; "));
+    }
+    Ok(out)
+}
+
+fn var_trace_exp(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let beta = number_arg(input, "beta")?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let z: f64 = symmetric_eigenvalues(matrix)
+        .iter()
+        .map(|&l| (-beta * l).exp())
+        .sum();
+    Ok(serde_json::json!({ "trace_exp": z }))
+}
+
+/// Matrix sign function by scaled Newton iteration `Z <- (cZ + (cZ)^-1) / 2`.
+fn matrix_sign(matrix: &DMatrix<f64>, tol: f64, max_iter: usize) -> Result<DMatrix<f64>, String> {
+    let n = matrix.nrows() as f64;
+    let mut z = matrix.clone();
+    for _ in 0..max_iter {
+        // |det Z|^(-1/n) from the LU diagonal in log space, since the
+        // determinant itself overflows or underflows for large Hamiltonians.
+        let lu = z.clone().lu();
+        let u_diag = lu.u().diagonal();
+        if u_diag.iter().any(|u| *u == 0.0 || !u.is_finite()) {
+            return Err("sign iteration hit a singular matrix".to_string());
+        }
+        let log_det: f64 = u_diag.iter().map(|u| u.abs().ln()).sum();
+        let c = (-log_det / n).exp();
+        let inv = lu
+            .try_inverse()
+            .ok_or_else(|| "sign iteration hit a singular matrix".to_string())?;
+        let next = (&z * c + inv / c) * 0.5;
+        let delta = (&next - &z).norm();
+        z = next;
+        if delta <= tol * z.norm() {
+            return Ok(z);
+        }
+    }
+    Err(format!(
+        "sign iteration failed to converge within {} iterations",
+        max_iter
+    ))
+}
+
+/// Stabilizing solution of `A^T X + X A - X B R^-1 B^T X + Q = 0`.
+///
+/// The stable invariant subspace `[I; X]` of the Hamiltonian
+/// `[[A, -B R^-1 B^T], [-Q, -A^T]]` is the null space of `sign(H) + I`.
+/// The Schur eigenvalues of `H` are checked first so that a Hamiltonian with
+/// imaginary-axis eigenvalues (no stabilizing solution) is rejected. nalgebra
+/// has no Schur reordering, which rules out the ordered-Schur method.
+fn solve_care(
+    a: &DMatrix<f64>,
+    b: &DMatrix<f64>,
+    q: &DMatrix<f64>,
+    r: &DMatrix<f64>,
+    sym_tol: f64,
+) -> Result<DMatrix<f64>, String> {
+    let n = a.nrows();
+    if !a.is_square() {
+        return Err("a must be square".to_string());
+    }
+    if b.nrows() != n {
+        return Err(format!("b must have {} rows, got {}", n, b.nrows()));
+    }
+    if q.shape() != (n, n) || !check_symmetric(q, sym_tol) {
+        return Err(format!("q must be a symmetric {}x{} matrix", n, n));
+    }
+    let m = b.ncols();
+    if r.shape() != (m, m) || !check_symmetric(r, sym_tol) {
+        return Err(format!("r must be a symmetric {}x{} matrix", m, m));
+    }
+    let r_inv = r
+        .clone()
+        .cholesky()
+        .ok_or_else(|| "r must be positive definite".to_string())?
+        .inverse();
+
+    let g = b * r_inv * b.transpose();
+    let mut h = DMatrix::zeros(2 * n, 2 * n);
+    h.view_mut((0, 0), (n, n)).copy_from(a);
+    h.view_mut((0, n), (n, n)).copy_from(&(-g));
+    h.view_mut((n, 0), (n, n)).copy_from(&(-q));
+    h.view_mut((n, n), (n, n)).copy_from(&(-a.transpose()));
+
+    let scale = h.norm().max(1.0);
+    let spectrum = general_eigenvalues(h.clone())?;
+    if spectrum.iter().any(|l| l.re.abs() <= 1.0e-10 * scale) {
+        return Err(
+            "hamiltonian has eigenvalues on the imaginary axis; no stabilizing solution"
+                .to_string(),
+        );
+    }
+
+    let w = matrix_sign(&h, 1.0e-13, 100)?;
+    let mut lhs = DMatrix::zeros(2 * n, n);
+    lhs.view_mut((0, 0), (n, n))
+        .copy_from(&w.view((0, n), (n, n)));
+    lhs.view_mut((n, 0), (n, n))
+        .copy_from(&(w.view((n, n), (n, n)) + DMatrix::identity(n, n)));
+    let mut rhs = DMatrix::zeros(2 * n, n);
+    rhs.view_mut((0, 0), (n, n))
+        .copy_from(&(-(w.view((0, 0), (n, n)) + DMatrix::identity(n, n))));
+    rhs.view_mut((n, 0), (n, n))
+        .copy_from(&(-w.view((n, 0), (n, n))));
+    let x = lhs.svd(true, true).solve(&rhs, 1.0e-14)?;
+    Ok((&x + x.transpose()) * 0.5)
+}
+
+fn var_care(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    let q = matrix_arg(input, "q")?;
+    let r = matrix_arg(input, "r")?;
+    let layout = output_layout(input)?;
+    let x = solve_care(&a, &b, &q, &r, sym_tol(input)?)?;
+    let r_inv = r
+        .clone()
+        .try_inverse()
+        .ok_or_else(|| "r is singular".to_string())?;
+    let residual = a.transpose() * &x + &x * &a - &x * &b * r_inv * b.transpose() * &x + &q;
+    Ok(serde_json::json!({ "x": matrix_value(&x, layout), "residual": residual.norm() }))
+}
+
+fn usize_arg(input: &Value, key: &str) -> Result<usize, String> {
+    input
+        .get(key)
+        .ok_or_else(|| format!("missing :{}", key))?
+        .as_u64()
+        .map(|k| k as usize)
+        .ok_or_else(|| format!("{} must be a non-negative integer", key))
+}
+
+struct LanczosRun {
+    ritz_values: Vec<f64>,
+    /// `beta_m * |y_i[m-1]|`, the residual norm of each Ritz pair.
+    ritz_residuals: Vec<f64>,
+    steps: usize,
+    breakdown: bool,
+}
+
+/// `k` Lanczos steps with full reorthogonalization from a fixed start vector.
+fn lanczos(matrix: &DMatrix<f64>, k: usize) -> LanczosRun {
+    let n = matrix.nrows();
+    let start = DVector::from_fn(n, |i, _| 1.0 + ((i + 1) as f64).sin() * 0.5);
+    let mut basis: Vec<DVector<f64>> = vec![start.normalize()];
+    let mut alphas: Vec<f64> = Vec::with_capacity(k);
+    let mut betas: Vec<f64> = Vec::with_capacity(k);
+    let mut breakdown = false;
+    let tol = 1.0e-12 * matrix.norm().max(1.0);
+
+    for j in 0..k {
+        let mut w = matrix * &basis[j];
+        let alpha = basis[j].dot(&w);
+        alphas.push(alpha);
+        for q in &basis {
+            let proj = q.dot(&w);
+            w -= q * proj;
+        }
+        let beta = w.norm();
+        betas.push(beta);
+        if beta <= tol {
+            breakdown = true;
+            break;
+        }
+        if j + 1 < k {
+            basis.push(w / beta);
+        }
+    }
+
+    let m = alphas.len();
+    let mut t = DMatrix::zeros(m, m);
+    for i in 0..m {
+        t[(i, i)] = alphas[i];
+        if i + 1 < m {
+            t[(i, i + 1)] = betas[i];
+            t[(i + 1, i)] = betas[i];
+        }
+    }
+    let (ritz_values, vectors) = sorted_symmetric_eigen(t);
+    let last_beta = if breakdown { 0.0 } else { betas[m - 1] };
+    let ritz_residuals = (0..m)
+        .map(|i| last_beta * vectors[(m - 1, i)].abs())
+        .collect();
+    LanczosRun {
+        ritz_values,
+        ritz_residuals,
+        steps: m,
+        breakdown,
+    }
+}
+
+fn var_lanczos(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let k = usize_arg(input, "k")?;
+    let n = matrix.nrows();
+    if k == 0 || k > n {
+        return Err(format!("k must satisfy 1 <= k <= {}, got {}", n, k));
+    }
+    let run = lanczos(&matrix, k);
+    let last = run.ritz_values.len() - 1;
+    Ok(serde_json::json!({
+        "largest": run.ritz_values[last],
+        "smallest": run.ritz_values[0],
+        "ritz_values": run.ritz_values,
+        "residuals": {
+            "largest": run.ritz_residuals[last],
+            "smallest": run.ritz_residuals[0],
+        },
+        "steps": run.steps,
+        "breakdown": run.breakdown,
+    }))
+}
+
+/// Applies `f` to the spectrum of a symmetric matrix: `V f(Λ) Vᵀ`.
+fn symmetric_function(matrix: &DMatrix<f64>, f: impl Fn(f64) -> f64) -> DMatrix<f64> {
+    let eigen = SymmetricEigen::new(matrix.clone());
+    let mapped = eigen.eigenvalues.map(f);
+    &eigen.eigenvectors * DMatrix::from_diagonal(&mapped) * eigen.eigenvectors.transpose()
+}
+
+fn check_spd(matrix: &DMatrix<f64>, what: &str, sym_tol: f64) -> Result<(), String> {
+    if !matrix.is_square() || !check_symmetric(matrix, sym_tol) {
+        return Err(format!("{} must be symmetric positive definite", what));
+    }
+    if matrix.clone().cholesky().is_none() {
+        return Err(format!("{} must be symmetric positive definite", what));
+    }
+    Ok(())
+}
+
+/// Riemannian geometric mean `A^½ (A^-½ B A^-½)^½ A^½` of two SPD matrices.
+fn spd_geometric_mean(a: &DMatrix<f64>, b: &DMatrix<f64>) -> DMatrix<f64> {
+    let a_half = symmetric_function(a, f64::sqrt);
+    let a_neg_half = symmetric_function(a, |l| 1.0 / l.sqrt());
+    let inner = &a_neg_half * b * &a_neg_half;
+    // Symmetrize to keep rounding from leaking into the eigensolver.
+    let inner = (&inner + inner.transpose()) * 0.5;
+    let mean = &a_half * symmetric_function(&inner, f64::sqrt) * &a_half;
+    (&mean + mean.transpose()) * 0.5
+}
+
+fn var_spd_geometric_mean(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    let tol = sym_tol(input)?;
+    check_spd(&a, "a", tol)?;
+    check_spd(&b, "b", tol)?;
+    if a.shape() != b.shape() {
+        return Err("a and b must have the same dimensions".to_string());
+    }
+    let layout = output_layout(input)?;
+    Ok(serde_json::json!({ "mean": matrix_value(&spd_geometric_mean(&a, &b), layout) }))
+}
+
+fn var_spectrum_centroid(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    let n = matrix.nrows() as f64;
+    if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+        let centroid = symmetric_eigenvalues(matrix).iter().sum::<f64>() / n;
+        Ok(serde_json::json!({ "centroid": centroid }))
+    } else {
+        let centroid = general_eigenvalues(matrix)?.iter().sum::<Complex<f64>>() / n;
+        Ok(serde_json::json!({ "centroid": [centroid.re, centroid.im] }))
+    }
+}
+
+/// Perron root and eigenvector of a non-negative matrix by power iteration on
+/// `A + I`; the shift keeps periodic (e.g. bipartite) matrices from cycling.
+fn perron_pair(
+    matrix: &DMatrix<f64>,
+    tol: f64,
+    max_iter: usize,
+) -> (f64, DVector<f64>, usize, bool) {
+    let n = matrix.nrows();
+    let shifted = matrix + DMatrix::<f64>::identity(n, n);
+    let mut v = DVector::from_element(n, 1.0 / n as f64);
+    for iter in 1..=max_iter {
+        let w = &shifted * &v;
+        let total = w.sum();
+        if total <= 0.0 {
+            return (0.0, v, iter, false);
+        }
+        let next = w / total;
+        let delta = (&next - &v).amax();
+        v = next;
+        if delta <= tol {
+            return ((matrix * &v).sum() / v.sum(), v, iter, true);
+        }
+    }
+    ((matrix * &v).sum() / v.sum(), v, max_iter, false)
+}
+
+fn var_perron(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    if let Some((idx, _)) = matrix.iter().enumerate().find(|(_, &x)| x < 0.0) {
+        // nalgebra storage is column-major.
+        let n = matrix.nrows();
+        return Err(format!(
+            "matrix must be non-negative, found negative entry at ({}, {})",
+            idx % n,
+            idx / n
+        ));
+    }
+    let (eigenvalue, vector, iterations, converged) = perron_pair(&matrix, 1.0e-13, 10_000);
+    Ok(serde_json::json!({
+        "eigenvalue": eigenvalue,
+        "eigenvector": vector.iter().cloned().collect::<Vec<f64>>(),
+        "iterations": iterations,
+        "converged": converged,
+    }))
+}
+
+/// Largest `|<c_i, c_j>| / (|c_i| |c_j|)` over distinct columns, with the pair.
+fn mutual_coherence(matrix: &DMatrix<f64>) -> Result<(f64, usize, usize), String> {
+    if matrix.ncols() < 2 {
+        return Err("coherence needs at least two columns".to_string());
+    }
+    let mut columns = Vec::with_capacity(matrix.ncols());
+    for (j, col) in matrix.column_iter().enumerate() {
+        let norm = col.norm();
+        if norm == 0.0 {
+            return Err(format!("column {} is zero", j));
+        }
+        columns.push(col / norm);
+    }
+    let mut best = (0.0, 0, 1);
+    for i in 0..columns.len() {
+        for j in (i + 1)..columns.len() {
+            let c = columns[i].dot(&columns[j]).abs();
+            if c > best.0 {
+                best = (c, i, j);
+            }
+        }
+    }
+    Ok(best)
+}
+
+fn var_coherence(input: &Value) -> Result<Value, String> {
+    let matrix = read_rect_matrix(input)?;
+    let (coherence, i, j) = mutual_coherence(&matrix)?;
+    Ok(serde_json::json!({ "coherence": coherence, "columns": [i, j] }))
+}
+
+fn var_spectrum_diff(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if !a.is_square() || a.shape() != b.shape() {
+        return Err(format!(
+            "a and b must be square with equal dimensions, got {}x{} and {}x{}",
+            a.nrows(),
+            a.ncols(),
+            b.nrows(),
+            b.ncols()
+        ));
+    }
+    if input_flag(input, "symmetric") {
+        let tol = sym_tol(input)?;
+        ensure_symmetric(&a, tol)?;
+        ensure_symmetric(&b, tol)?;
+        let ea = symmetric_eigenvalues(a);
+        let eb = symmetric_eigenvalues(b);
+        let diff: Vec<f64> = eb.iter().zip(ea.iter()).map(|(y, x)| y - x).collect();
+        let max_abs = diff.iter().fold(0.0f64, |m, d| m.max(d.abs()));
+        Ok(serde_json::json!({ "a": ea, "b": eb, "diff": diff, "max_abs_diff": max_abs }))
+    } else {
+        let ea = general_eigenvalues(a)?;
+        let eb = general_eigenvalues(b)?;
+        let diff: Vec<Complex<f64>> = eb.iter().zip(ea.iter()).map(|(y, x)| y - x).collect();
+        let max_abs = diff.iter().fold(0.0f64, |m, d| m.max(d.norm()));
+        Ok(serde_json::json!({
+            "a": complex_pairs(&ea),
+            "b": complex_pairs(&eb),
+            "diff": complex_pairs(&diff),
+            "max_abs_diff": max_abs,
+        }))
+    }
+}
+
+fn var_departure_from_normality(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let fro2 = matrix.norm_squared();
+    let spectral: f64 = general_eigenvalues(matrix)?
+        .iter()
+        .map(|l| l.norm_sqr())
+        .sum();
+    // Rounding can push a normal matrix's difference slightly negative.
+    let departure = (fro2 - spectral).max(0.0).sqrt();
+    Ok(serde_json::json!({ "departure": departure }))
+}
+
+fn var_verify(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    if !check_symmetric(&matrix, sym_tol(input)?) {
+        return Err("verify requires a symmetric matrix".to_string());
+    }
+    let n = matrix.nrows();
+    let (values, vectors) = sorted_symmetric_eigen(matrix.clone());
+    let max_residual = (0..n)
+        .map(|i| {
+            let v = vectors.column(i);
+            (&matrix * v - v * values[i]).norm()
+        })
+        .fold(0.0f64, f64::max);
+    let orthogonality = (vectors.transpose() * &vectors - DMatrix::<f64>::identity(n, n)).norm();
+    let d = DMatrix::from_diagonal(&DVector::from_vec(values.clone()));
+    let reconstruction = (&vectors * d * vectors.transpose() - &matrix).norm();
+    Ok(serde_json::json!({
+        "eigenvalues": values,
+        "max_residual": max_residual,
+        "orthogonality_error": orthogonality,
+        "reconstruction_error": reconstruction,
+    }))
+}
+
+fn var_balance(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let (balanced, scaling) = balance_matrix(&matrix);
+    Ok(serde_json::json!({
+        "balanced": matrix_value(&balanced, layout),
+        "scaling": scaling,
+        "norm_before": matrix.norm(),
+        "norm_after": balanced.norm(),
+    }))
+}
+
+const MAX_SWEEP_POINTS: usize = 1024;
+
+fn var_eig_sweep(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if !a.is_square() || a.shape() != b.shape() {
+        return Err("a and b must be square with equal dimensions".to_string());
+    }
+    let ts = input
+        .get("ts")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "ts must be a vector of numbers".to_string())?;
+    if ts.len() > MAX_SWEEP_POINTS {
+        return Err(format!(
+            "ts has {} points; at most {} allowed",
+            ts.len(),
+            MAX_SWEEP_POINTS
+        ));
+    }
+    let opts = EigOptions::from_input(input)?;
+    // Checked between points, so at least one point is always computed.
+    let deadline = batch_deadline(input)?;
+    let started = Instant::now();
+    let mut results = Vec::with_capacity(ts.len());
+    for t in ts {
+        if let Some(deadline) = deadline {
+            if !results.is_empty() && started.elapsed() >= deadline {
+                break;
+            }
+        }
+        let t = json_number_to_f64(t)
+            .filter(|t| t.is_finite())
+            .ok_or_else(|| "ts entries must be finite numbers".to_string())?;
+        let mut point = eigenvalues_for(&a + &b * t, &opts)?;
+        point["t"] = serde_json::json!(t);
+        results.push(point);
+    }
+    let mut out = serde_json::json!({ "results": results });
+    if deadline.is_some() {
+        out["remaining"] = serde_json::json!(ts.len() - results.len());
+    }
+    Ok(out)
+}
+
+/// Optional `:batch_timeout_ms` budget shared by sweeps and batch invokes.
+fn batch_deadline(input: &Value) -> Result<Option<std::time::Duration>, String> {
+    match input.get("batch_timeout_ms") {
+        None => Ok(None),
+        Some(_) => Ok(Some(std::time::Duration::from_millis(
+            usize_arg(input, "batch_timeout_ms")? as u64,
+        ))),
+    }
+}
+
+fn json_vector(v: &Value, what: &str) -> Result<DVector<f64>, String> {
+    v.as_array()
+        .ok_or_else(|| format!("{} must be a vector of numbers", what))?
+        .iter()
+        .map(|x| json_number_to_f64(x).ok_or_else(|| format!("{} entries must be numbers", what)))
+        .collect::<Result<Vec<f64>, String>>()
+        .map(DVector::from_vec)
+}
+
+fn vector_arg(input: &Value, key: &str) -> Result<DVector<f64>, String> {
+    json_vector(
+        input.get(key).ok_or_else(|| format!("missing :{}", key))?,
+        key,
+    )
+}
+
+fn vector_list(input: &Value, key: &str) -> Result<Vec<DVector<f64>>, String> {
+    input
+        .get(key)
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| format!("{} must be a vector of vectors", key))?
+        .iter()
+        .map(|item| json_vector(item, key))
+        .collect()
+}
+
+/// One step of Oja's subspace rule, `W <- W + η (x - W y) yᵀ` with `y = Wᵀx`,
+/// followed by QR re-orthonormalization of the columns of `W`.
+fn oja_step(basis: &DMatrix<f64>, x: &DVector<f64>, rate: f64) -> DMatrix<f64> {
+    let y = basis.transpose() * x;
+    let updated = basis + (x - basis * &y) * y.transpose() * rate;
+    updated.qr().q()
+}
+
+fn var_subspace_update(input: &Value) -> Result<Value, String> {
+    let basis = vector_list(input, "basis")?;
+    let x = vector_arg(input, "x")?;
+    let rate = number_arg(input, "rate")?;
+    if rate <= 0.0 {
+        return Err("rate must be positive".to_string());
+    }
+    let n = x.len();
+    if basis.is_empty() || basis.len() > n {
+        return Err(format!("basis must hold between 1 and {} vectors", n));
+    }
+    if let Some(i) = basis.iter().position(|b| b.len() != n) {
+        return Err(format!(
+            "basis vector {} has length {}, expected {}",
+            i,
+            basis[i].len(),
+            n
+        ));
+    }
+    let w = oja_step(&DMatrix::from_columns(&basis), &x, rate);
+    let updated: Vec<Vec<f64>> = w
+        .column_iter()
+        .map(|c| c.iter().cloned().collect())
+        .collect();
+    Ok(serde_json::json!({ "basis": updated }))
+}
+
+/// Singular values below this are treated as zero (LAPACK's default rcond).
+fn svd_cutoff(m: &DMatrix<f64>, singular_values: &DVector<f64>) -> f64 {
+    let largest = singular_values.iter().cloned().fold(0.0, f64::max);
+    m.nrows().max(m.ncols()) as f64 * f64::EPSILON * largest
+}
+
+fn var_lstsq(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = vector_arg(input, "b")?;
+    if a.nrows() == 0 || a.ncols() == 0 {
+        return Err("a must be non-empty".to_string());
+    }
+    if b.len() != a.nrows() {
+        return Err(format!(
+            "b must have length {} to match a's rows, got {}",
+            a.nrows(),
+            b.len()
+        ));
+    }
+    let svd = a.clone().svd(true, true);
+    let cutoff = svd_cutoff(&a, &svd.singular_values);
+    let rank = svd.singular_values.iter().filter(|&&s| s > cutoff).count();
+    let x = svd.solve(&b, cutoff)?;
+    let residual = (&a * &x - &b).norm();
+    Ok(serde_json::json!({
+        "x": x.iter().cloned().collect::<Vec<f64>>(),
+        "residual_norm": residual,
+        "rank": rank,
+    }))
+}
+
+fn sorted_singular_values(matrix: &DMatrix<f64>) -> Vec<f64> {
+    let mut values: Vec<f64> = matrix.singular_values().iter().cloned().collect();
+    values.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    values
+}
+
+fn var_svd_energy(input: &Value) -> Result<Value, String> {
+    let matrix = read_rect_matrix(input)?;
+    let values = sorted_singular_values(&matrix);
+    let total: f64 = values.iter().map(|s| s * s).sum();
+    if total == 0.0 {
+        return Err("matrix is zero; energy fractions are undefined".to_string());
+    }
+    let mut running = 0.0;
+    let cumulative: Vec<f64> = values
+        .iter()
+        .map(|s| {
+            running += s * s;
+            running / total
+        })
+        .collect();
+    Ok(serde_json::json!({ "singular_values": values, "cumulative_energy": cumulative }))
+}
+
+/// Truncated SVD `U_k Σ_k V_kᵀ` and the Frobenius error `sqrt(Σ_{i>k} σ_i²)`.
+fn low_rank_approx(matrix: &DMatrix<f64>, k: usize) -> (DMatrix<f64>, f64) {
+    let svd = matrix.clone().svd(true, true);
+    let (u, v_t) = (svd.u.unwrap(), svd.v_t.unwrap());
+    let sigma = &svd.singular_values;
+    let approx =
+        u.columns(0, k) * DMatrix::from_diagonal(&sigma.rows(0, k).into_owned()) * v_t.rows(0, k);
+    let error = sigma.iter().skip(k).map(|s| s * s).sum::<f64>().sqrt();
+    (approx, error)
+}
+
+fn var_low_rank_approx(input: &Value) -> Result<Value, String> {
+    let matrix = read_rect_matrix(input)?;
+    let layout = output_layout(input)?;
+    let k = usize_arg(input, "k")?;
+    let max_rank = matrix.nrows().min(matrix.ncols());
+    if k > max_rank {
+        return Err(format!("k must satisfy 0 <= k <= {}, got {}", max_rank, k));
+    }
+    let (approx, error) = low_rank_approx(&matrix, k);
+    Ok(serde_json::json!({ "approximation": matrix_value(&approx, layout), "error": error }))
+}
+
+fn var_sparsity(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let tol = tolerance_arg(input, "zero_tol", 1.0e-12)?;
+    let (rows, cols) = matrix.shape();
+    let mut zeros = 0usize;
+    let mut bandwidth = 0usize;
+    for i in 0..rows {
+        for j in 0..cols {
+            if matrix[(i, j)].abs() <= tol {
+                zeros += 1;
+            } else {
+                bandwidth = bandwidth.max(i.abs_diff(j));
+            }
+        }
+    }
+    let total = rows * cols;
+    Ok(serde_json::json!({
+        "zeros": zeros,
+        "zero_fraction": zeros as f64 / total.max(1) as f64,
+        "bandwidth": bandwidth,
+        "is_banded": bandwidth + 1 < rows.max(cols),
+    }))
+}
+
+fn checked_vectors(input: &Value, key: &str, n: usize) -> Result<Vec<DVector<f64>>, String> {
+    let vectors = vector_list(input, key)?;
+    if let Some(i) = vectors.iter().position(|v| v.len() != n) {
+        return Err(format!(
+            "{} entry {} has length {}, expected {}",
+            key,
+            i,
+            vectors[i].len(),
+            n
+        ));
+    }
+    Ok(vectors)
+}
+
+fn var_apply(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let vectors = checked_vectors(input, "vectors", matrix.ncols())?;
+    let results: Vec<Vec<f64>> = vectors
+        .iter()
+        .map(|v| (&matrix * v).iter().cloned().collect())
+        .collect();
+    Ok(serde_json::json!({ "results": results }))
+}
+
+/// Groups ascending values into runs whose consecutive gaps are `<= tol`.
+fn cluster_sorted(values: &[f64], tol: f64) -> Vec<Vec<f64>> {
+    let mut clusters: Vec<Vec<f64>> = Vec::new();
+    for &v in values {
+        match clusters.last_mut() {
+            Some(last) if v - last[last.len() - 1] <= tol => last.push(v),
+            _ => clusters.push(vec![v]),
+        }
+    }
+    clusters
+}
+
+fn var_common_invariant_subspaces(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let tol = tolerance_arg(input, "cluster_tol", 1.0e-8)?;
+    let values = symmetric_eigenvalues(matrix);
+    let scale = values.iter().fold(1.0f64, |m, v| m.max(v.abs()));
+    let clusters = cluster_sorted(&values, tol * scale);
+    let dimensions: Vec<usize> = clusters.iter().map(|c| c.len()).collect();
+    let subspaces: Vec<Value> = clusters
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "eigenvalue": c.iter().sum::<f64>() / c.len() as f64,
+                "dimension": c.len(),
+            })
+        })
+        .collect();
+    // Matrices commuting with a symmetric A act independently on each
+    // eigenspace, so the commutant has dimension Σ m_i².
+    let commutant: usize = dimensions.iter().map(|m| m * m).sum();
+    Ok(serde_json::json!({
+        "subspaces": subspaces,
+        "dimensions": dimensions,
+        "commutant_dimension": commutant,
+    }))
+}
+
+fn var_frobenius_inner(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if a.shape() != b.shape() {
+        return Err(format!(
+            "a is {}x{} but b is {}x{}",
+            a.nrows(),
+            a.ncols(),
+            b.nrows(),
+            b.ncols()
+        ));
+    }
+    Ok(serde_json::json!({ "inner": a.dot(&b) }))
+}
+
+/// Spectral gain `g(λ)` parsed from a `:filter` spec.
+enum SpectralFilter {
+    /// Pass eigenvalues in `[lo, hi]`, zero the rest.
+    Range(f64, f64),
+    /// Ascending `[threshold, gain]` steps; below the first threshold the gain is 1.
+    Steps(Vec<(f64, f64)>),
+}
+
+impl SpectralFilter {
+    fn from_input(input: &Value) -> Result<Self, String> {
+        let spec = input.get("filter").ok_or("missing :filter")?;
+        let pair = |v: &Value, what: &str| -> Result<(f64, f64), String> {
+            match v.as_array().map(|a| a.as_slice()) {
+                Some([a, b]) => match (json_number_to_f64(a), json_number_to_f64(b)) {
+                    (Some(a), Some(b)) if a.is_finite() && b.is_finite() => Ok((a, b)),
+                    _ => Err(format!("{} must hold finite numbers", what)),
+                },
+                _ => Err(format!("{} must be a pair", what)),
+            }
+        };
+        match (spec.get("range"), spec.get("gains")) {
+            (Some(range), None) => {
+                let (lo, hi) = pair(range, "filter range")?;
+                if lo > hi {
+                    return Err("filter range must have lo <= hi".to_string());
+                }
+                Ok(SpectralFilter::Range(lo, hi))
+            }
+            (None, Some(gains)) => {
+                let steps = gains
+                    .as_array()
+                    .ok_or("filter gains must be a vector of [threshold gain] pairs")?
+                    .iter()
+                    .map(|g| pair(g, "filter gain"))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if steps.windows(2).any(|w| w[0].0 >= w[1].0) {
+                    return Err("filter thresholds must be strictly ascending".to_string());
+                }
+                Ok(SpectralFilter::Steps(steps))
+            }
+            _ => Err("filter must have exactly one of :range or :gains".to_string()),
+        }
+    }
+
+    fn gain(&self, lambda: f64) -> f64 {
+        match self {
+            SpectralFilter::Range(lo, hi) => {
+                if (*lo..=*hi).contains(&lambda) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            SpectralFilter::Steps(steps) => steps
+                .iter()
+                .take_while(|(threshold, _)| *threshold <= lambda)
+                .last()
+                .map_or(1.0, |(_, gain)| *gain),
+        }
+    }
+}
+
+fn var_spectral_filter(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let filter = SpectralFilter::from_input(input)?;
+    let layout = output_layout(input)?;
+    let gains: Vec<f64> = symmetric_eigenvalues(matrix.clone())
+        .into_iter()
+        .map(|l| filter.gain(l))
+        .collect();
+    let filtered = symmetric_function(&matrix, |l| filter.gain(l) * l);
+    Ok(serde_json::json!({
+        "filtered": matrix_value(&filtered, layout),
+        "gains": gains,
+    }))
+}
+
+fn check_skew_symmetric(m: &DMatrix<f64>, eps: f64) -> bool {
+    let n = m.nrows();
+    (0..n).all(|i| (i..n).all(|j| (m[(i, j)] + m[(j, i)]).abs() <= eps))
+}
+
+/// Pfaffian by the Parlett–Reid skew-symmetric `L T Lᵀ` elimination with
+/// partial pivoting; each pivot swap flips the sign.
+fn pfaffian(matrix: &DMatrix<f64>) -> f64 {
+    let mut a = matrix.clone();
+    let n = a.nrows();
+    let mut pf = 1.0;
+    for k in (0..n.saturating_sub(1)).step_by(2) {
+        let kp = (k + 1..n)
+            .max_by(|&x, &y| a[(x, k)].abs().total_cmp(&a[(y, k)].abs()))
+            .unwrap_or(k + 1);
+        if kp != k + 1 {
+            a.swap_rows(k + 1, kp);
+            a.swap_columns(k + 1, kp);
+            pf = -pf;
+        }
+        if a[(k + 1, k)] == 0.0 {
+            return 0.0;
+        }
+        pf *= a[(k, k + 1)];
+        if k + 2 < n {
+            let tau = a.view((k, k + 2), (1, n - k - 2)).transpose() / a[(k, k + 1)];
+            let col = a.view((k + 2, k + 1), (n - k - 2, 1)).into_owned();
+            let update = &tau * col.transpose() - &col * tau.transpose();
+            let mut rest = a.view_mut((k + 2, k + 2), (n - k - 2, n - k - 2));
+            rest += update;
+        }
+    }
+    pf
+}
+
+fn var_pfaffian(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    if matrix.nrows() % 2 != 0 {
+        return Err("pfaffian needs an even-dimensional matrix".to_string());
+    }
+    if !check_skew_symmetric(&matrix, sym_tol(input)?) {
+        return Err("matrix is not skew-symmetric within epsilon".to_string());
+    }
+    Ok(serde_json::json!({ "pfaffian": pfaffian(&matrix) }))
+}
+
+/// Orthonormal basis for the span of `vectors`, which must be independent.
+fn orthonormal_span(vectors: &[DVector<f64>], key: &str) -> Result<DMatrix<f64>, String> {
+    let qr = DMatrix::from_columns(vectors).qr();
+    let r = qr.r();
+    let scale = vectors.iter().fold(0.0f64, |m, v| m.max(v.norm()));
+    if (0..r.ncols()).any(|i| r[(i, i)].abs() <= 1.0e-10 * scale.max(f64::MIN_POSITIVE)) {
+        return Err(format!("{} vectors must be linearly independent", key));
+    }
+    Ok(qr.q())
+}
+
+fn var_principal_angles(input: &Value) -> Result<Value, String> {
+    let a = vector_list(input, "a")?;
+    let b = vector_list(input, "b")?;
+    let n = a
+        .first()
+        .map(|v| v.len())
+        .ok_or("a must hold at least one vector")?;
+    if b.is_empty() {
+        return Err("b must hold at least one vector".to_string());
+    }
+    for (key, set) in [("a", &a), ("b", &b)] {
+        if let Some(i) = set.iter().position(|v| v.len() != n) {
+            return Err(format!(
+                "{} entry {} has length {}, expected {}",
+                key,
+                i,
+                set[i].len(),
+                n
+            ));
+        }
+        if set.len() > n {
+            return Err(format!("{} holds more than {} vectors", key, n));
+        }
+    }
+    let qa = orthonormal_span(&a, "a")?;
+    let qb = orthonormal_span(&b, "b")?;
+    let cosines = sorted_singular_values(&(qa.transpose() * qb));
+    let angles: Vec<f64> = cosines.iter().map(|c| c.clamp(-1.0, 1.0).acos()).collect();
+    Ok(serde_json::json!({ "angles": angles, "cosines": cosines }))
+}
+
+fn var_logdet_spd(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    if !check_symmetric(&matrix, sym_tol(input)?) {
+        return Err("matrix must be symmetric positive definite".to_string());
+    }
+    let chol = matrix
+        .cholesky()
+        .ok_or("matrix must be symmetric positive definite")?;
+    let logdet = 2.0
+        * chol
+            .l_dirty()
+            .diagonal()
+            .iter()
+            .map(|d| d.ln())
+            .sum::<f64>();
+    Ok(serde_json::json!({ "logdet": logdet }))
+}
+
+fn var_trace_inv_product(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if !a.is_square() {
+        return Err("a must be square".to_string());
+    }
+    if b.nrows() != a.nrows() || !b.is_square() {
+        return Err(format!("b must be {}x{}", a.nrows(), a.nrows()));
+    }
+    // Solve A X = B instead of forming A⁻¹.
+    let x = a.lu().solve(&b).ok_or("a is singular")?;
+    Ok(serde_json::json!({ "trace": x.trace() }))
+}
+
+fn var_involution(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let tol = tolerance_arg(input, "tol", 1.0e-9)?;
+    let n = matrix.nrows();
+    let residual = (&matrix * &matrix - DMatrix::identity(n, n)).norm();
+    Ok(serde_json::json!({ "involution": residual <= tol, "residual": residual }))
+}
+
+fn var_eigenvalue_multiplicity(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    let target = number_arg(input, "eigenvalue")?;
+    // Defective eigenvalues split by roughly eps^(1/k), so the default is loose.
+    let tol = tolerance_arg(input, "tol", 1.0e-6)?;
+    let n = matrix.nrows();
+    let scale = tol * matrix.norm().max(1.0);
+    let algebraic = if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+        symmetric_eigenvalues(matrix.clone())
+            .iter()
+            .filter(|l| (*l - target).abs() <= scale)
+            .count()
+    } else {
+        general_eigenvalues(matrix.clone())?
+            .iter()
+            .filter(|l| (*l - target).norm() <= scale)
+            .count()
+    };
+    let shifted = &matrix - DMatrix::identity(n, n) * target;
+    let geometric = sorted_singular_values(&shifted)
+        .iter()
+        .filter(|s| **s <= scale)
+        .count();
+    Ok(serde_json::json!({
+        "algebraic": algebraic,
+        "geometric": geometric,
+        "defective": geometric < algebraic,
+    }))
+}
+
+fn var_procrustes(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if a.shape() != b.shape() {
+        return Err("a and b must have the same dimensions".to_string());
+    }
+    let svd = (&b * a.transpose()).svd(true, true);
+    let (u, v_t) = match (svd.u, svd.v_t) {
+        (Some(u), Some(v_t)) => (u, v_t),
+        _ => return Err("svd failed to produce singular vectors".to_string()),
+    };
+    let rotation = u * v_t;
+    let residual = (&rotation * &a - &b).norm();
+    let layout = output_layout(input)?;
+    Ok(serde_json::json!({
+        "rotation": matrix_value(&rotation, layout),
+        "residual": residual,
+    }))
+}
+
+/// Reads a complex scalar given as a number or an `[re im]` pair.
+fn complex_arg(input: &Value, key: &str) -> Result<Complex<f64>, String> {
+    let v = input.get(key).ok_or_else(|| format!("missing :{}", key))?;
+    let parts = match v {
+        Value::Array(pair) if pair.len() == 2 => {
+            (json_number_to_f64(&pair[0]), json_number_to_f64(&pair[1]))
+        }
+        _ => (json_number_to_f64(v), Some(0.0)),
+    };
+    match parts {
+        (Some(re), Some(im)) if re.is_finite() && im.is_finite() => Ok(Complex::new(re, im)),
+        _ => Err(format!("{} must be a finite number or [re im] pair", key)),
+    }
+}
+
+const NUMERICAL_RANGE_DIRECTIONS: usize = 360;
+
+/// Approximate membership of `z` in the field of values `W(A)`. `W(A)` is
+/// the intersection over θ of the half-planes `Re(e^{-iθ} w) <= λ_max(H_θ)`
+/// with `H_θ` the Hermitian part of `e^{-iθ} A`; this samples `directions`
+/// angles and returns the smallest slack (negative when `z` is cut off).
+fn numerical_range_margin(matrix: &DMatrix<f64>, z: Complex<f64>, directions: usize) -> f64 {
+    let sym = (matrix + matrix.transpose()) * 0.5;
+    let skew = (matrix - matrix.transpose()) * 0.5;
+    (0..directions)
+        .map(|k| {
+            let theta = 2.0 * std::f64::consts::PI * k as f64 / directions as f64;
+            let (sin, cos) = theta.sin_cos();
+            let h = DMatrix::from_fn(matrix.nrows(), matrix.ncols(), |i, j| {
+                Complex::new(cos * sym[(i, j)], -sin * skew[(i, j)])
+            });
+            let lambda_max = SymmetricEigen::new(h)
+                .eigenvalues
+                .iter()
+                .cloned()
+                .fold(f64::NEG_INFINITY, f64::max);
+            lambda_max - (z * Complex::from_polar(1.0, -theta)).re
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn var_in_numerical_range(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let z = complex_arg(input, "z")?;
+    let tol = tolerance_arg(input, "tol", 1.0e-9)?;
+    let directions = match input.get("directions") {
+        None => NUMERICAL_RANGE_DIRECTIONS,
+        Some(_) => usize_arg(input, "directions")?,
+    };
+    if directions < 3 {
+        return Err("directions must be at least 3".to_string());
+    }
+    let margin = numerical_range_margin(&matrix, z, directions);
+    Ok(serde_json::json!({ "contained": margin >= -tol, "margin": margin }))
+}
+
+fn var_weighted_eigs(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let weights = vector_arg(input, "weights")?;
+    if weights.len() != matrix.nrows() {
+        return Err(format!(
+            "weights has length {}, expected {}",
+            weights.len(),
+            matrix.nrows()
+        ));
+    }
+    if weights.iter().any(|w| !(w.is_finite() && *w > 0.0)) {
+        return Err("weights must be finite and positive".to_string());
+    }
+    // A v = λ W v with W = diag(w) becomes C u = λ u for C = W^-½ A W^-½.
+    let inv_sqrt = weights.map(|w| 1.0 / w.sqrt());
+    let scaled = DMatrix::from_fn(matrix.nrows(), matrix.ncols(), |i, j| {
+        inv_sqrt[i] * matrix[(i, j)] * inv_sqrt[j]
+    });
+    Ok(serde_json::json!({ "eigenvalues": symmetric_eigenvalues(scaled) }))
+}
+
+/// Rotates a unit vector so its largest-magnitude entry is real and positive.
+fn normalize_phase(v: DVector<Complex<f64>>) -> DVector<Complex<f64>> {
+    let pivot = v
+        .iter()
+        .cloned()
+        .max_by(|a, b| a.norm().total_cmp(&b.norm()))
+        .unwrap_or(Complex::new(1.0, 0.0));
+    if pivot.norm() == 0.0 {
+        return v;
+    }
+    let phase = pivot.conj() / pivot.norm();
+    v.map(|x| x * phase)
+}
+
+/// Eigenvectors for sorted general eigenvalues, from the null space of
+/// `A − λI`. Equal eigenvalues share one SVD and take its `k` smallest
+/// singular directions; a defective matrix has fewer independent ones.
+/// The grouping tolerance is tight so that the scattered copies of a
+/// defective eigenvalue stay apart and yield (near-)parallel vectors.
+fn general_eigenvectors(
+    matrix: &DMatrix<f64>,
+    values: &[Complex<f64>],
+) -> Vec<DVector<Complex<f64>>> {
+    let n = matrix.nrows();
+    let tol = 1.0e-10 * matrix.norm().max(1.0);
+    let complex = matrix.map(|x| Complex::new(x, 0.0));
+    let mut vectors = Vec::with_capacity(values.len());
+    let mut i = 0;
+    while i < values.len() {
+        let lambda = values[i];
+        let k = values[i..]
+            .iter()
+            .take_while(|v| (*v - lambda).norm() <= tol)
+            .count();
+        let shifted = &complex - DMatrix::identity(n, n) * lambda;
+        let svd = shifted.svd(false, true);
+        let v_t = svd.v_t.expect("right singular vectors were requested");
+        let mut order: Vec<usize> = (0..svd.singular_values.len()).collect();
+        order.sort_by(|&a, &b| svd.singular_values[a].total_cmp(&svd.singular_values[b]));
+        for &idx in order.iter().take(k) {
+            vectors.push(normalize_phase(v_t.row(idx).adjoint()));
+        }
+        i += k;
+    }
+    vectors
+}
+
+/// 2-norm condition number `σ_max / σ_min` of the eigenvector matrix; the
+/// Bauer–Fike bound scales eigenvalue perturbations by it.
+fn eigenvector_condition(matrix: &DMatrix<f64>, values: &[Complex<f64>]) -> f64 {
+    let vectors = general_eigenvectors(matrix, values);
+    let v = DMatrix::from_columns(&vectors);
+    let sv = v.singular_values();
+    let max = sv.iter().cloned().fold(0.0, f64::max);
+    let min = sv.iter().cloned().fold(f64::INFINITY, f64::min);
+    if min == 0.0 {
+        f64::INFINITY
+    } else {
+        max / min
+    }
+}
+
+fn var_eigenvectors(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let (eigenvalues, rows): (Value, Vec<Vec<Value>>) = if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+        let (values, vectors) = sorted_symmetric_eigen(matrix);
+        let rows = vectors
+            .column_iter()
+            .map(|c| {
+                let v = normalize_phase(c.map(|x| Complex::new(x, 0.0)));
+                v.iter().map(|x| serde_json::json!(x.re)).collect()
+            })
+            .collect();
+        (serde_json::json!(values), rows)
+    } else {
+        let values = general_eigenvalues(matrix.clone())?;
+        let rows = general_eigenvectors(&matrix, &values)
+            .iter()
+            .map(|v| v.iter().map(|x| serde_json::json!([x.re, x.im])).collect())
+            .collect();
+        (serde_json::json!(complex_pairs(&values)), rows)
+    };
+    // Row layout: eigenvectors[i] is the eigenvector of eigenvalues[i].
+    // Column layout: eigenvectors[j][i] is entry j of that eigenvector.
+    let nested: Vec<Vec<Value>> = match layout {
+        Layout::Row => rows,
+        Layout::Column => (0..rows.first().map_or(0, |r| r.len()))
+            .map(|j| rows.iter().map(|r| r[j].clone()).collect())
+            .collect(),
+    };
+    Ok(serde_json::json!({
+        "eigenvalues": eigenvalues,
+        "eigenvectors": nested,
+        "eigenvector_layout": if layout == Layout::Row { "row" } else { "column" },
+    }))
+}
+
+/// `det(A) = ±Π σ_i`. `U` and `Vᵀ` are orthogonal, so their determinants
+/// are ±1 and only their signs are taken from LU.
+fn svd_determinant(matrix: DMatrix<f64>) -> Result<f64, String> {
+    let svd = matrix.svd(true, true);
+    let (u, v_t) = match (svd.u, svd.v_t) {
+        (Some(u), Some(v_t)) => (u, v_t),
+        _ => return Err("svd failed to produce singular vectors".to_string()),
+    };
+    let sign = u.determinant().signum() * v_t.determinant().signum();
+    Ok(sign * svd.singular_values.iter().product::<f64>())
+}
+
+/// Determinant as the product of the eigenvalues by default, reusing the
+/// same decomposition as `eigenvalues` so both vars agree on a given matrix.
+/// `:method "svd"` and `"lu"` give independent estimates for cross-checks.
+fn var_determinant(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    match input.get("method").map(|v| v.as_str()) {
+        None | Some(Some("eigen")) => {}
+        Some(Some("svd")) => {
+            return Ok(serde_json::json!({ "determinant": svd_determinant(matrix)? }))
+        }
+        Some(Some("lu")) => return Ok(serde_json::json!({ "determinant": matrix.determinant() })),
+        Some(_) => return Err("method must be \"eigen\", \"svd\" or \"lu\"".to_string()),
+    }
+    if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+        let det: f64 = symmetric_eigenvalues(matrix).iter().product();
+        return Ok(serde_json::json!({ "determinant": det }));
+    }
+    let det = general_eigenvalues(matrix)?
+        .iter()
+        .fold(Complex::new(1.0, 0.0), |acc, l| acc * l);
+    if det.im == 0.0 {
+        return Ok(serde_json::json!({ "determinant": det.re }));
+    }
+    // Conjugate pairs cancel exactly in theory; what is left is rounding.
+    let warning = format!("eigenvalue product has imaginary residual {:e}", det.im);
+    if det.im.abs() <= 1.0e-9 * det.norm().max(1.0) {
+        Ok(serde_json::json!({ "determinant": det.re, "warning": warning }))
+    } else {
+        Ok(serde_json::json!({ "determinant": [det.re, det.im], "warning": warning }))
+    }
+}
+
+fn var_deviatoric(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let n = matrix.nrows();
+    let isotropic = DMatrix::identity(n, n) * (matrix.trace() / n as f64);
+    let mut out =
+        serde_json::json!({ "deviatoric": matrix_value(&(&matrix - &isotropic), layout) });
+    if input_flag(input, "with_isotropic") {
+        out["isotropic"] = matrix_value(&isotropic, layout);
+    }
+    Ok(out)
+}
+
+fn var_spectral_spread(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    let values: Vec<Complex<f64>> = if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+        symmetric_eigenvalues(matrix)
+            .into_iter()
+            .map(|l| Complex::new(l, 0.0))
+            .collect()
+    } else {
+        general_eigenvalues(matrix)?
+    };
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<Complex<f64>>() / n;
+    let std_dev = (values.iter().map(|l| (l - mean).norm_sqr()).sum::<f64>() / n).sqrt();
+    // The diameter of a finite point set equals that of its convex hull.
+    let spread = values
+        .iter()
+        .flat_map(|a| values.iter().map(move |b| (a - b).norm()))
+        .fold(0.0, f64::max);
+    let gaps: Vec<f64> = values.windows(2).map(|w| (w[1] - w[0]).norm()).collect();
+    let mean_gap = if gaps.is_empty() {
+        0.0
+    } else {
+        gaps.iter().sum::<f64>() / gaps.len() as f64
+    };
+    Ok(serde_json::json!({ "spread": spread, "std_dev": std_dev, "mean_gap": mean_gap }))
+}
+
+fn var_nearest_orthogonal(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    // The orthogonal polar factor U Vᵀ minimizes ||Q − A||_F over orthogonal Q.
+    let svd = matrix.clone().svd(true, true);
+    let (u, v_t) = match (svd.u, svd.v_t) {
+        (Some(u), Some(v_t)) => (u, v_t),
+        _ => return Err("svd failed to produce singular vectors".to_string()),
+    };
+    let q = u * v_t;
+    Ok(serde_json::json!({
+        "orthogonal": matrix_value(&q, layout),
+        "distance": (&q - &matrix).norm(),
+    }))
+}
+
+fn var_cond_fro(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let norm = matrix.norm();
+    let inverse = matrix.try_inverse().ok_or("matrix is singular")?;
+    Ok(serde_json::json!({ "cond": norm * inverse.norm() }))
+}
+
+fn var_split_spectrum(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let h = (&matrix + matrix.transpose()) * 0.5;
+    let k = (&matrix - matrix.transpose()) * 0.5;
+    // K is real skew-symmetric: iK is Hermitian, so K's spectrum is ±iμ for
+    // the real eigenvalues μ of iK.
+    let ik = k.map(|x| Complex::new(0.0, x));
+    let mut skew: Vec<Complex<f64>> = SymmetricEigen::new(ik)
+        .eigenvalues
+        .iter()
+        .map(|mu| Complex::new(0.0, -mu))
+        .collect();
+    skew.sort_by(cmp_complex);
+    Ok(serde_json::json!({
+        "symmetric": symmetric_eigenvalues(h),
+        "skew": complex_pairs(&skew),
+    }))
+}
+
+fn var_inverse(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+    }
+    let layout = output_layout(input)?;
+    let singular = "matrix is singular or ill-conditioned".to_string();
+    // LU happily inverts numerically singular input into huge or NaN
+    // entries, so screen the smallest singular value first.
+    let sv = matrix.singular_values();
+    let smallest = sv.iter().cloned().fold(f64::INFINITY, f64::min);
+    if smallest <= svd_cutoff(&matrix, &sv) {
+        return Err(singular);
+    }
+    let inverse = matrix.try_inverse().ok_or_else(|| singular.clone())?;
+    if inverse.iter().any(|x| !x.is_finite()) {
+        return Err(singular);
+    }
+    Ok(serde_json::json!({ "inverse": matrix_value(&inverse, layout) }))
+}
+
+fn var_quadratic_form(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let vectors = checked_vectors(input, "vectors", matrix.ncols())?;
+    let values: Vec<f64> = vectors.iter().map(|x| x.dot(&(&matrix * x))).collect();
+    Ok(serde_json::json!({ "values": values }))
+}
+
+/// `L = B Bᵀ` from an oriented `:incidence` matrix `B` (one row per vertex,
+/// one column per edge), or `None` when the graph is given as a matrix.
+fn laplacian_from_incidence(input: &Value) -> Result<Option<DMatrix<f64>>, String> {
+    if input.get("incidence").is_none() {
+        return Ok(None);
+    }
+    if input.get("rows").is_some() || input.get("data").is_some() {
+        return Err("pass either :incidence or an adjacency matrix, not both".to_string());
+    }
+    let b = matrix_arg(input, "incidence")?;
+    if b.nrows() == 0 {
+        return Err("incidence must have one row per vertex".to_string());
+    }
+    Ok(Some(&b * b.transpose()))
+}
+
+fn var_laplacian_components(input: &Value) -> Result<Value, String> {
+    let laplacian = match laplacian_from_incidence(input)? {
+        Some(laplacian) => laplacian,
+        None => {
+            let (matrix, _) = build_matrix(input)?;
+            ensure_symmetric(&matrix, sym_tol(input)?)?;
+            if input_flag(input, "is_laplacian") {
+                matrix
+            } else {
+                // Treat the input as a weighted adjacency matrix: L = D − A.
+                if matrix.iter().any(|w| *w < 0.0) {
+                    return Err("adjacency weights must be non-negative".to_string());
+                }
+                let degrees: Vec<f64> = matrix.row_iter().map(|r| r.sum()).collect();
+                DMatrix::from_diagonal(&DVector::from_vec(degrees)) - matrix
+            }
+        }
+    };
+    let tol = tolerance_arg(input, "zero_tol", 1.0e-9)?;
+    let values = symmetric_eigenvalues(laplacian);
+    let scale = values.iter().fold(1.0f64, |m, l| m.max(l.abs()));
+    let components = values.iter().filter(|l| l.abs() <= tol * scale).count();
+    Ok(serde_json::json!({
+        "eigenvalues": values,
+        "num_components": components,
+        "algebraic_connectivity": values.get(1),
+    }))
+}
+
+fn var_bracket_eigs(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if !a.is_square() || a.shape() != b.shape() {
+        return Err("a and b must be square with the same dimensions".to_string());
+    }
+    let bracket = &a * &b - &b * &a;
+    let values = general_eigenvalues(bracket.clone())?;
+    let sum = values.iter().sum::<Complex<f64>>();
+    // [A, B] is traceless, so the eigenvalues must sum to ~0.
+    let trace_tol = 1.0e-9 * (a.norm() * b.norm()).max(1.0);
+    Ok(serde_json::json!({
+        "eigenvalues": complex_pairs(&values),
+        "eigenvalue_sum": [sum.re, sum.im],
+        "traceless": sum.norm() <= trace_tol && bracket.trace().abs() <= trace_tol,
+    }))
+}
+
+fn var_stationary(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let tol = tolerance_arg(input, "tol", 1.0e-9)?;
+    // Work with the row-stochastic form P, where π P = π.
+    let mut p = match input.get("stochastic").map(|v| v.as_str()) {
+        None | Some(Some("row")) => matrix,
+        Some(Some("column")) => matrix.transpose(),
+        _ => return Err("stochastic must be \"row\" or \"column\"".to_string()),
+    };
+    if p.iter().any(|x| *x < 0.0) {
+        return Err("stochastic matrix entries must be non-negative".to_string());
+    }
+    let normalize = input_flag(input, "normalize");
+    for (i, mut row) in p.row_iter_mut().enumerate() {
+        let sum = row.sum();
+        if normalize && sum > 0.0 {
+            row /= sum;
+        } else if (sum - 1.0).abs() > tol {
+            return Err(format!("row {} sums to {}, not 1", i, sum));
+        }
+    }
+    let values = general_eigenvalues(p.clone())?;
+    let multiplicity = values
+        .iter()
+        .filter(|l| (*l - 1.0).norm() <= tol.max(1.0e-8))
+        .count();
+    // The null vector of Pᵀ − I is the stationary distribution.
+    let n = p.nrows();
+    let shifted = p.transpose() - DMatrix::identity(n, n);
+    let v_t = shifted.svd(false, true).v_t.unwrap();
+    let pi = v_t.row(n - 1).transpose();
+    let pi = &pi / pi.sum();
+    let mut out = serde_json::json!({
+        "stationary": pi.as_slice(),
+        "eigenvalue_one_multiplicity": multiplicity,
+    });
+    if normalize {
+        out["matrix"] = matrix_value(&p, layout);
+    }
+    if multiplicity > 1 {
+        out["warning"] = Value::String(format!(
+            "eigenvalue 1 has multiplicity {}; the chain is reducible and the stationary distribution is not unique",
+            multiplicity
+        ));
+    }
+    Ok(out)
+}
+
+fn var_trace(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    Ok(serde_json::json!({ "trace": matrix.trace() }))
+}
+
+fn var_rank(input: &Value) -> Result<Value, String> {
+    let matrix = read_rect_matrix(input)?;
+    let singular_values = matrix.clone().svd(false, false).singular_values;
+    let tol = match input.get("rank_tol") {
+        Some(_) => tolerance_arg(input, "rank_tol", 0.0)?,
+        None => svd_cutoff(&matrix, &singular_values),
+    };
+    let rank = singular_values.iter().filter(|&&s| s > tol).count();
+    Ok(serde_json::json!({ "rank": rank, "tol": tol }))
+}
+
+/// Matrices at least this large are counted with the contour integral
+/// unless `:method` says otherwise.
+const CONTOUR_MIN_DIM: usize = 128;
+
+enum Region {
+    Disk { center: Complex<f64>, radius: f64 },
+    Rect { re: (f64, f64), im: (f64, f64) },
+}
+
+impl Region {
+    fn from_input(input: &Value) -> Result<Self, String> {
+        let pair = |key: &str| -> Result<(f64, f64), String> {
+            match input
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|a| a.as_slice())
+            {
+                Some([lo, hi]) => match (json_number_to_f64(lo), json_number_to_f64(hi)) {
+                    (Some(lo), Some(hi)) if lo.is_finite() && hi.is_finite() && lo < hi => {
+                        Ok((lo, hi))
+                    }
+                    _ => Err(format!(
+                        "{} must be a finite [lo hi] pair with lo < hi",
+                        key
+                    )),
+                },
+                _ => Err(format!("{} must be a [lo hi] pair", key)),
+            }
+        };
+        match (input.get("radius"), input.get("re")) {
+            (Some(_), None) => {
+                let radius = number_arg(input, "radius")?;
+                if radius <= 0.0 {
+                    return Err("radius must be positive".to_string());
+                }
+                Ok(Region::Disk {
+                    center: complex_arg(input, "center")?,
+                    radius,
+                })
+            }
+            (None, Some(_)) => Ok(Region::Rect {
+                re: pair("re")?,
+                im: pair("im")?,
+            }),
+            _ => Err("give either :center and :radius or :re and :im".to_string()),
+        }
+    }
+
+    fn contains(&self, z: Complex<f64>) -> bool {
+        match self {
+            Region::Disk { center, radius } => (z - center).norm() <= *radius,
+            Region::Rect { re, im } => re.0 <= z.re && z.re <= re.1 && im.0 <= z.im && z.im <= im.1,
+        }
+    }
+
+    /// Quadrature nodes `(z, dz)` traversing the boundary counter-clockwise:
+    /// the trapezoid rule on a circle, the midpoint rule on each rectangle edge.
+    fn contour(&self, points: usize) -> Vec<(Complex<f64>, Complex<f64>)> {
+        match self {
+            Region::Disk { center, radius } => (0..points)
+                .map(|k| {
+                    let theta = 2.0 * std::f64::consts::PI * k as f64 / points as f64;
+                    let w = Complex::from_polar(*radius, theta);
+                    (
+                        center + w,
+                        Complex::<f64>::i() * w * (2.0 * std::f64::consts::PI / points as f64),
+                    )
+                })
+                .collect(),
+            Region::Rect { re, im } => {
+                let corners = [
+                    Complex::new(re.0, im.0),
+                    Complex::new(re.1, im.0),
+                    Complex::new(re.1, im.1),
+                    Complex::new(re.0, im.1),
+                ];
+                let per_edge = points.div_ceil(4);
+                (0..4)
+                    .flat_map(|e| {
+                        let (a, b) = (corners[e], corners[(e + 1) % 4]);
+                        let dz = (b - a) / per_edge as f64;
+                        (0..per_edge).map(move |k| (a + dz * (k as f64 + 0.5), dz))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// `(1/2πi) ∮ tr((zI − A)⁻¹) dz`, which is the number of eigenvalues
+/// enclosed by the contour when the quadrature has converged.
+fn contour_eigen_count(
+    matrix: &DMatrix<f64>,
+    nodes: &[(Complex<f64>, Complex<f64>)],
+) -> Result<Complex<f64>, String> {
+    let n = matrix.nrows();
+    let a = matrix.map(|x| Complex::new(x, 0.0));
+    let mut total = Complex::new(0.0, 0.0);
+    for (z, dz) in nodes {
+        let shifted = DMatrix::from_diagonal_element(n, n, *z) - &a;
+        let resolvent = shifted
+            .lu()
+            .try_inverse()
+            .ok_or("an eigenvalue lies on the contour")?;
+        total += resolvent.trace() * dz;
+    }
+    Ok(total / Complex::new(0.0, 2.0 * std::f64::consts::PI))
+}
+
+fn var_count_in_region(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let region = Region::from_input(input)?;
+    let contour = match input.get("method").map(|v| v.as_str()) {
+        None => matrix.nrows() >= CONTOUR_MIN_DIM,
+        Some(Some("eigenvalues")) => false,
+        Some(Some("contour")) => true,
+        _ => return Err("method must be \"eigenvalues\" or \"contour\"".to_string()),
+    };
+    if !contour {
+        let count = general_eigenvalues(matrix)?
+            .into_iter()
+            .filter(|l| region.contains(*l))
+            .count();
+        return Ok(serde_json::json!({ "count": count, "method": "eigenvalues" }));
+    }
+    let points = match input.get("quadrature_points") {
+        None => 256,
+        Some(_) => usize_arg(input, "quadrature_points")?,
+    };
+    if points < 8 {
+        return Err("quadrature_points must be at least 8".to_string());
+    }
+    let integral = contour_eigen_count(&matrix, &region.contour(points))?;
+    Ok(serde_json::json!({
+        "count": integral.re.round().max(0.0) as usize,
+        "method": "contour",
+        "integral": [integral.re, integral.im],
+    }))
+}
+
+fn bool_mask(input: &Value, n: usize) -> Result<Vec<Vec<bool>>, String> {
+    let rows = input
+        .get("mask")
+        .and_then(|v| v.as_array())
+        .ok_or("mask must be a vector of rows of booleans")?;
+    if rows.len() != n {
+        return Err(format!("mask has {} rows, expected {}", rows.len(), n));
+    }
+    rows.iter()
+        .map(|row| {
+            let row = row.as_array().ok_or("mask rows must be vectors")?;
+            if row.len() != n {
+                return Err(format!("mask rows must have length {}", n));
+            }
+            row.iter()
+                .map(|b| {
+                    b.as_bool()
+                        .ok_or_else(|| "mask entries must be booleans".to_string())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn var_masked_symmetrize(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let n = matrix.nrows();
+    let mask = bool_mask(input, n)?;
+    let tol = tolerance_arg(input, "tol", 1.0e-9)?;
+    // Per pair (i, j), (j, i): a trusted entry is copied across, otherwise
+    // the average is the nearest symmetric value in Frobenius norm.
+    let mut out = matrix.clone();
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let (a, b) = (matrix[(i, j)], matrix[(j, i)]);
+            let value = match (mask[i][j], mask[j][i]) {
+                (true, true) if (a - b).abs() > tol * a.abs().max(b.abs()).max(1.0) => {
+                    return Err(format!(
+                        "trusted entries ({},{}) and ({},{}) disagree",
+                        i, j, j, i
+                    ));
+                }
+                (true, _) => a,
+                (false, true) => b,
+                (false, false) => 0.5 * (a + b),
+            };
+            out[(i, j)] = value;
+            out[(j, i)] = value;
+        }
+    }
+    Ok(serde_json::json!({
+        "symmetrized": matrix_value(&out, layout),
+        "distance": (&out - &matrix).norm(),
+    }))
+}
+
+fn var_interpolate(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let b = matrix_arg(input, "b")?;
+    if !a.is_square() || a.shape() != b.shape() {
+        return Err("a and b must be square with the same dimensions".to_string());
+    }
+    let t = number_arg(input, "t")?;
+    if !(0.0..=1.0).contains(&t) {
+        return Err("t must lie in [0, 1]".to_string());
+    }
+    let layout = output_layout(input)?;
+    let interpolated = match input.get("mode").map(|v| v.as_str()) {
+        None | Some(Some("linear")) => &a * (1.0 - t) + &b * t,
+        Some(Some("log-euclidean")) => {
+            let tol = sym_tol(input)?;
+            check_spd(&a, "a", tol)?;
+            check_spd(&b, "b", tol)?;
+            // exp((1 − t) log A + t log B) stays SPD for every t.
+            let log =
+                symmetric_function(&a, f64::ln) * (1.0 - t) + symmetric_function(&b, f64::ln) * t;
+            let log = (&log + log.transpose()) * 0.5;
+            let out = symmetric_function(&log, f64::exp);
+            (&out + out.transpose()) * 0.5
+        }
+        _ => return Err("mode must be \"linear\" or \"log-euclidean\"".to_string()),
+    };
+    Ok(serde_json::json!({ "matrix": matrix_value(&interpolated, layout) }))
+}
+
+fn var_svd(input: &Value) -> Result<Value, String> {
+    let matrix = read_rect_matrix(input)?;
+    if input_flag(input, "values_only") {
+        return Ok(serde_json::json!({ "singular_values": sorted_singular_values(&matrix) }));
+    }
+    let layout = output_layout(input)?;
+    let svd = nalgebra::SVD::new(matrix, true, true);
+    let (u, v_t) = match (svd.u, svd.v_t) {
+        (Some(u), Some(v_t)) => (u, v_t),
+        _ => return Err("svd failed to produce singular vectors".to_string()),
+    };
+    // `SVD::new` already returns the singular values in descending order.
+    let values: Vec<f64> = svd.singular_values.iter().cloned().collect();
+    Ok(serde_json::json!({
+        "singular_values": values,
+        "u": matrix_value(&u, layout),
+        "v": matrix_value(&v_t.transpose(), layout),
+    }))
+}
+
+fn var_deflated_eigs(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    ensure_symmetric(&matrix, sym_tol(input)?)?;
+    let n = matrix.nrows();
+    let vectors = checked_vectors(input, "deflate", n)?;
+    if vectors.is_empty() || vectors.len() >= n {
+        return Err(format!("deflate must hold between 1 and {} vectors", n - 1));
+    }
+    let q = orthonormal_span(&vectors, "deflate")?;
+    // The eigenvalue-1 eigenvectors of I − QQᵀ span the orthogonal
+    // complement; restricting A to it drops the deflated modes entirely.
+    let projector = DMatrix::identity(n, n) - &q * q.transpose();
+    let (_, basis) = sorted_symmetric_eigen(projector);
+    let w = basis.columns(vectors.len(), n - vectors.len()).into_owned();
+    let restricted = w.transpose() * &matrix * &w;
+    let restricted = (&restricted + restricted.transpose()) * 0.5;
+    Ok(serde_json::json!({ "eigenvalues": symmetric_eigenvalues(restricted) }))
+}
+
+fn var_transform_eigs(input: &Value) -> Result<Value, String> {
+    let a = matrix_arg(input, "a")?;
+    let p = matrix_arg(input, "p")?;
+    if !a.is_square() || a.shape() != p.shape() {
+        return Err("a and p must be square with the same dimensions".to_string());
+    }
+    let p_inv = p.clone().try_inverse().ok_or("p is singular")?;
+    let transformed = &p_inv * &a * &p;
+    let original = general_eigenvalues(a)?;
+    let values = general_eigenvalues(transformed)?;
+    // Pair each transformed eigenvalue with the nearest unused original one.
+    let mut unused = original.clone();
+    let mut max_discrepancy = 0.0f64;
+    for value in &values {
+        let (i, distance) = unused
+            .iter()
+            .map(|o| (o - value).norm())
+            .enumerate()
+            .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap_or(Ordering::Equal))
+            .ok_or("eigenvalue counts differ")?;
+        unused.swap_remove(i);
+        max_discrepancy = max_discrepancy.max(distance);
+    }
+    Ok(serde_json::json!({
+        "eigenvalues": complex_pairs(&values),
+        "original": complex_pairs(&original),
+        "max_discrepancy": max_discrepancy,
+    }))
+}
+
+/// One eigenvalue group of `real-jordan`, with block sizes read off the
+/// rank sequence `r_k = rank((A − λI)^k)`: `r_{k−1} − r_k` blocks have size
+/// at least `k`.
+struct JordanGroup {
+    value: Complex<f64>,
+    algebraic: usize,
+    geometric: usize,
+    block_sizes: Vec<usize>,
+    spread: f64,
+    rank_gap: f64,
+    consistent: bool,
+}
+
+fn jordan_group(matrix: &DMatrix<f64>, cluster: &[Complex<f64>], rank_tol: f64) -> JordanGroup {
+    let n = matrix.nrows();
+    let m = cluster.len();
+    let value = cluster.iter().sum::<Complex<f64>>() / m as f64;
+    let spread = cluster
+        .iter()
+        .map(|z| (z - value).norm())
+        .fold(0.0, f64::max);
+    let shifted =
+        matrix.map(|x| Complex::new(x, 0.0)) - DMatrix::from_diagonal_element(n, n, value);
+    let mut power = DMatrix::identity(n, n);
+    let mut ranks = vec![n];
+    let mut rank_gap = f64::MAX;
+    for _ in 0..m {
+        power = &power * &shifted;
+        let mut sv: Vec<f64> = power
+            .clone()
+            .svd(false, false)
+            .singular_values
+            .iter()
+            .cloned()
+            .collect();
+        sv.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let cutoff = rank_tol * sv[0].max(1.0);
+        let rank = sv.iter().filter(|&&s| s > cutoff).count();
+        // How cleanly the kept singular values separate from the dropped ones.
+        if rank > 0 && rank < n {
+            rank_gap = rank_gap.min(sv[rank - 1] / sv[rank].max(f64::MIN_POSITIVE));
+        }
+        ranks.push(rank);
+    }
+    // at_least[k] = number of blocks of size >= k + 1.
+    let at_least: Vec<usize> = ranks
+        .windows(2)
+        .map(|w| w[0].saturating_sub(w[1]))
+        .collect();
+    let mut block_sizes = Vec::new();
+    for k in (0..m).rev() {
+        let exact = at_least[k].saturating_sub(at_least.get(k + 1).copied().unwrap_or(0));
+        block_sizes.extend(std::iter::repeat_n(k + 1, exact));
+    }
+    let consistent = block_sizes.iter().sum::<usize>() == m;
+    JordanGroup {
+        value,
+        algebraic: m,
+        geometric: at_least[0],
+        block_sizes,
+        spread,
+        rank_gap,
+        consistent,
+    }
+}
+
+/// Real Jordan form: `λ` blocks with ones above the diagonal, and for a
+/// pair `a ± bi` 2×2 `[[a b] [−b a]]` blocks with `I₂` above them.
+fn real_jordan_form(groups: &[JordanGroup], n: usize) -> DMatrix<f64> {
+    let mut form = DMatrix::zeros(n, n);
+    let mut at = 0;
+    for group in groups {
+        let pair = group.value.im != 0.0;
+        let width = if pair { 2 } else { 1 };
+        for &size in &group.block_sizes {
+            for k in 0..size {
+                let i = at + k * width;
+                if pair {
+                    let (a, b) = (group.value.re, group.value.im);
+                    form[(i, i)] = a;
+                    form[(i, i + 1)] = b;
+                    form[(i + 1, i)] = -b;
+                    form[(i + 1, i + 1)] = a;
+                } else {
+                    form[(i, i)] = group.value.re;
+                }
+                if k + 1 < size {
+                    for d in 0..width {
+                        form[(i + d, i + width + d)] = 1.0;
+                    }
+                }
+            }
+            at += size * width;
+        }
+    }
+    form
+}
+
+fn var_real_jordan(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let n = matrix.nrows();
+    let values = general_eigenvalues(matrix.clone())?;
+    let max_abs = values.iter().fold(0.0f64, |m, l| m.max(l.norm()));
+    // A Jordan block of size k perturbed by ε splits by ~ε^(1/k), so the
+    // default clustering tolerance is far looser than the solver's.
+    let cluster_tol = tolerance_arg(input, "cluster_tol", 1.0e-6)? * max_abs.max(1.0);
+    let rank_tol = tolerance_arg(input, "rank_tol", 1.0e-8)?;
+    // Real eigenvalues and the upper half-plane are clustered separately, so
+    // each upper cluster stands for itself and its mirrored conjugate; the
+    // greedy clustering could otherwise split a value and its conjugate into
+    // differently sized groups.
+    let (real, upper): (Vec<Complex<f64>>, Vec<Complex<f64>>) = values
+        .iter()
+        .filter(|l| l.im >= -cluster_tol)
+        .partition(|l| l.im <= cluster_tol);
+    let mut groups = Vec::new();
+    for cluster in cluster_complex(&real, cluster_tol) {
+        let mut group = jordan_group(&matrix, &cluster, rank_tol);
+        group.value.im = 0.0;
+        groups.push(group);
+    }
+    for cluster in cluster_complex(&upper, cluster_tol) {
+        groups.push(jordan_group(&matrix, &cluster, rank_tol));
+    }
+    groups.sort_by(|a, b| cmp_complex(&a.value, &b.value));
+    let report: Vec<Value> = groups
+        .iter()
+        .map(|g| {
+            let confident = g.consistent && g.rank_gap >= 1.0e6 && g.spread <= cluster_tol;
+            serde_json::json!({
+                "eigenvalue": [g.value.re, g.value.im],
+                "conjugate_pair": g.value.im != 0.0,
+                "algebraic": g.algebraic,
+                "geometric": g.geometric,
+                "block_sizes": g.block_sizes,
+                "spread": g.spread,
+                "rank_gap": g.rank_gap,
+                "confidence": if confident { "high" } else { "low" },
+            })
+        })
+        .collect();
+    let width: usize = groups
+        .iter()
+        .map(|g| g.block_sizes.iter().sum::<usize>() * if g.value.im != 0.0 { 2 } else { 1 })
+        .sum();
+    let form = if width == n && groups.iter().all(|g| g.consistent) {
+        matrix_value(&real_jordan_form(&groups, n), layout)
+    } else {
+        Value::Null
+    };
+    Ok(serde_json::json!({
+        "groups": report,
+        "defective": groups.iter().any(|g| g.geometric < g.algebraic),
+        "form": form,
+    }))
+}
+
+/// `A^k` by repeated squaring, `O(log k)` products.
+fn matrix_power(matrix: &DMatrix<f64>, mut k: u64) -> DMatrix<f64> {
+    let n = matrix.nrows();
+    let mut result = DMatrix::identity(n, n);
+    let mut base = matrix.clone();
+    while k > 0 {
+        if k & 1 == 1 {
+            result = &result * &base;
+        }
+        k >>= 1;
+        if k > 0 {
+            base = &base * &base;
+        }
+    }
+    result
+}
+
+fn var_power(input: &Value) -> Result<Value, String> {
+    let (matrix, _) = build_matrix(input)?;
+    let layout = output_layout(input)?;
+    let exponent = input
+        .get("exponent")
+        .ok_or("missing :exponent")?
+        .as_i64()
+        .ok_or("exponent must be an integer")?;
+    let base = if exponent < 0 {
+        matrix
+            .try_inverse()
+            .ok_or("matrix is singular; negative powers need an inverse")?
+    } else {
+        matrix
+    };
+    let powered = matrix_power(&base, exponent.unsigned_abs());
+    if powered.iter().any(|x| !x.is_finite()) {
+        return Err("matrix power overflowed".to_string());
+    }
+    Ok(serde_json::json!({ "power": matrix_value(&powered, layout) }))
+}
+
+fn var_spectral_radius(input: &Value) -> Result<Value, String> {
+    let (matrix, symmetric) = build_matrix(input)?;
+    let radius = if symmetric {
+        ensure_symmetric(&matrix, sym_tol(input)?)?;
+        symmetric_eigenvalues(matrix)
+            .iter()
+            .fold(0.0f64, |m, l| m.max(l.abs()))
+    } else {
+        general_eigenvalues(matrix)?
+            .iter()
+            .fold(0.0f64, |m, l| m.max(l.norm()))
+    };
+    Ok(serde_json::json!({ "spectral_radius": radius }))
+}
+
+const NAMESPACE: &str = "pod.eigs";
+
+struct VarSpec {
+    name: &'static str,
+    doc: &'static str,
+    run: fn(&Value) -> Result<Value, String>,
+}
+
+const VARS: &[VarSpec] = &[
+    VarSpec {
+        name: "eigenvalues",
+        doc: "Compute eigenvalues for a square matrix.",
+        run: var_eigenvalues,
+    },
+    VarSpec {
+        name: "dsum",
+        doc: "Direct sum of :a and :b as a block-diagonal matrix.",
+        run: var_dsum,
+    },
+    VarSpec {
+        name: "mean",
+        doc: "Element-wise mean of :matrices, optionally with its eigenvalues.",
+        run: var_mean,
+    },
+    VarSpec {
+        name: "density-eigs",
+        doc: "Eigenvalues of a density matrix as probabilities, plus von Neumann entropy.",
+        run: var_density_eigs,
+    },
+    VarSpec {
+        name: "trace-exp",
+        doc: "Partition function trace(exp(-beta A)) of a symmetric matrix.",
+        run: var_trace_exp,
+    },
+    VarSpec {
+        name: "care",
+        doc: "Stabilizing solution X of the continuous algebraic Riccati equation for :a :b :q :r, via the Hamiltonian sign function (not ordered Schur).",
+        run: var_care,
+    },
+    VarSpec {
+        name: "lanczos",
+        doc: "Extreme Ritz values of a symmetric matrix after :k Lanczos steps.",
+        run: var_lanczos,
+    },
+    VarSpec {
+        name: "spd-geometric-mean",
+        doc: "Riemannian geometric mean A # B of two SPD matrices :a and :b.",
+        run: var_spd_geometric_mean,
+    },
+    VarSpec {
+        name: "spectrum-centroid",
+        doc: "Mean of the eigenvalues (trace/n), real when symmetric, else [re im].",
+        run: var_spectrum_centroid,
+    },
+    VarSpec {
+        name: "perron",
+        doc: "Perron root and non-negative eigenvector (sum 1) of a non-negative matrix.",
+        run: var_perron,
+    },
+    VarSpec {
+        name: "coherence",
+        doc: "Mutual coherence: max normalized inner product between distinct columns.",
+        run: var_coherence,
+    },
+    VarSpec {
+        name: "spectrum-diff",
+        doc: "Sorted eigenvalues of :a and :b with their element-wise difference (b - a).",
+        run: var_spectrum_diff,
+    },
+    VarSpec {
+        name: "departure-from-normality",
+        doc: "Henrici's departure from normality sqrt(||A||_F^2 - sum |lambda_i|^2).",
+        run: var_departure_from_normality,
+    },
+    VarSpec {
+        name: "verify",
+        doc: "Accuracy report for the symmetric eigendecomposition: residual, orthogonality, reconstruction.",
+        run: var_verify,
+    },
+    VarSpec {
+        name: "balance",
+        doc: "Parlett-Reinsch balanced matrix D^-1 A D and the diagonal scaling D.",
+        run: var_balance,
+    },
+    VarSpec {
+        name: "eig-sweep",
+        doc: "Eigenvalues of :a + t :b for each t in :ts.",
+        run: var_eig_sweep,
+    },
+    VarSpec {
+        name: "subspace-update",
+        doc: "One Oja step updating an orthonormal :basis with sample :x at :rate.",
+        run: var_subspace_update,
+    },
+    VarSpec {
+        name: "lstsq",
+        doc: "Minimum-norm least-squares solution of :a x = :b via SVD, with residual norm.",
+        run: var_lstsq,
+    },
+    VarSpec {
+        name: "svd-energy",
+        doc: "Descending singular values with the cumulative fraction of sum sigma^2 captured by the top k.",
+        run: var_svd_energy,
+    },
+    VarSpec {
+        name: "low-rank-approx",
+        doc: "Best rank-:k approximation by truncated SVD, with its Frobenius error.",
+        run: var_low_rank_approx,
+    },
+    VarSpec {
+        name: "sparsity",
+        doc: "Count and fraction of near-zero entries plus the detected bandwidth.",
+        run: var_sparsity,
+    },
+    VarSpec {
+        name: "apply",
+        doc: "Matrix-vector products A v for each v in :vectors.",
+        run: var_apply,
+    },
+    VarSpec {
+        name: "common-invariant-subspaces",
+        doc: "Degenerate eigenvalue clusters of a symmetric matrix and their invariant subspace dimensions.",
+        run: var_common_invariant_subspaces,
+    },
+    VarSpec {
+        name: "frobenius-inner",
+        doc: "Frobenius inner product <A, B>_F = trace(A^T B) of two same-shaped matrices.",
+        run: var_frobenius_inner,
+    },
+    VarSpec {
+        name: "spectral-filter",
+        doc: "Reconstruct a symmetric matrix as sum g(λ_i) λ_i v_i v_i^T for a range or step-gain filter.",
+        run: var_spectral_filter,
+    },
+    VarSpec {
+        name: "pfaffian",
+        doc: "Pfaffian of an even-dimensional skew-symmetric matrix (pf(A)^2 = det(A)).",
+        run: var_pfaffian,
+    },
+    VarSpec {
+        name: "principal-angles",
+        doc: "Principal angles (radians) between the spans of two sets of vectors.",
+        run: var_principal_angles,
+    },
+    VarSpec {
+        name: "logdet-spd",
+        doc: "log det(A) of an SPD matrix as 2 sum log L_ii from its Cholesky factor.",
+        run: var_logdet_spd,
+    },
+    VarSpec {
+        name: "trace-inv-product",
+        doc: "trace(A^-1 B) by an LU solve of A X = B, without forming A^-1.",
+        run: var_trace_inv_product,
+    },
+    VarSpec {
+        name: "involution?",
+        doc: "Whether A^2 = I within :tol, with the residual ||A^2 - I||_F.",
+        run: var_involution,
+    },
+    VarSpec {
+        name: "eigenvalue-multiplicity",
+        doc: "Algebraic and geometric multiplicity of a given real eigenvalue.",
+        run: var_eigenvalue_multiplicity,
+    },
+    VarSpec {
+        name: "procrustes",
+        doc: "Orthogonal R minimizing ||R A - B||_F (orthogonal Procrustes) and the residual.",
+        run: var_procrustes,
+    },
+    VarSpec {
+        name: "in-numerical-range?",
+        doc: "Approximate test whether a complex point lies in the field of values of A.",
+        run: var_in_numerical_range,
+    },
+    VarSpec {
+        name: "weighted-eigs",
+        doc: "Eigenvalues of A v = λ diag(w) v for symmetric A and positive weights w.",
+        run: var_weighted_eigs,
+    },
+    VarSpec {
+        name: "eigenvectors",
+        doc: "Eigenvalues with matching unit eigenvectors, one per row by default.",
+        run: var_eigenvectors,
+    },
+    VarSpec {
+        name: "determinant",
+        doc: "Determinant by :method eigen (default; product of the eigenvalues), svd or lu.",
+        run: var_determinant,
+    },
+    VarSpec {
+        name: "deviatoric",
+        doc: "Trace-free part A - (trace(A)/n) I, optionally with the isotropic part.",
+        run: var_deviatoric,
+    },
+    VarSpec {
+        name: "spectral-spread",
+        doc: "Spectrum spread (diameter), standard deviation and mean gap between sorted eigenvalues.",
+        run: var_spectral_spread,
+    },
+    VarSpec {
+        name: "nearest-orthogonal",
+        doc: "Nearest orthogonal matrix in Frobenius norm (polar factor U V^T) and its distance.",
+        run: var_nearest_orthogonal,
+    },
+    VarSpec {
+        name: "cond-fro",
+        doc: "Frobenius-norm condition number ||A||_F ||A^-1||_F.",
+        run: var_cond_fro,
+    },
+    VarSpec {
+        name: "split-spectrum",
+        doc: "Eigenvalues of the symmetric part (A + A^T)/2 and of the skew part (A - A^T)/2.",
+        run: var_split_spectrum,
+    },
+    VarSpec {
+        name: "inverse",
+        doc: "Matrix inverse, or an error when the matrix is singular or ill-conditioned.",
+        run: var_inverse,
+    },
+    VarSpec {
+        name: "quadratic-form",
+        doc: "x^T A x for each vector x under :vectors, with A symmetric.",
+        run: var_quadratic_form,
+    },
+    VarSpec {
+        name: "laplacian-components",
+        doc: "Graph Laplacian spectrum (from adjacency, Laplacian or :incidence) with connected-component count and algebraic connectivity.",
+        run: var_laplacian_components,
+    },
+    VarSpec {
+        name: "bracket-eigs",
+        doc: "Eigenvalues of the commutator [A, B] = AB - BA, with a traceless sanity check.",
+        run: var_bracket_eigs,
+    },
+    VarSpec {
+        name: "stationary",
+        doc: "Stationary distribution of a row- or column-stochastic matrix (eigenvector for eigenvalue 1, summing to 1).",
+        run: var_stationary,
+    },
+    VarSpec {
+        name: "trace",
+        doc: "Sum of the diagonal entries.",
+        run: var_trace,
+    },
+    VarSpec {
+        name: "rank",
+        doc: "Numerical rank: the number of singular values above a tolerance.",
+        run: var_rank,
+    },
+    VarSpec {
+        name: "count-in-region",
+        doc: "Number of eigenvalues inside a disk or rectangle of the complex plane, directly or by contour integration.",
+        run: var_count_in_region,
+    },
+    VarSpec {
+        name: "masked-symmetrize",
+        doc: "Nearest symmetric matrix that keeps the entries a boolean mask marks as trusted.",
+        run: var_masked_symmetrize,
+    },
+    VarSpec {
+        name: "interpolate",
+        doc: "Interpolate between A and B at t in [0, 1], linearly or log-Euclidean for SPD matrices.",
+        run: var_interpolate,
+    },
+    VarSpec {
+        name: "svd",
+        doc: "Singular value decomposition A = U diag(σ) Vᵀ with σ sorted descending.",
+        run: var_svd,
+    },
+    VarSpec {
+        name: "deflated-eigs",
+        doc: "Eigenvalues of a symmetric matrix restricted to the orthogonal complement of given vectors.",
+        run: var_deflated_eigs,
+    },
+    VarSpec {
+        name: "transform-eigs",
+        doc: "Eigenvalues of P^-1 A P beside those of A, with the largest discrepancy between them.",
+        run: var_transform_eigs,
+    },
+    VarSpec {
+        name: "real-jordan",
+        doc: "Best-effort real Jordan structure: eigenvalue groups, block sizes and confidence indicators.",
+        run: var_real_jordan,
+    },
+    VarSpec {
+        name: "power",
+        doc: "Integer matrix power A^k by repeated squaring; negative k uses the inverse.",
+        run: var_power,
+    },
+    VarSpec {
+        name: "spectral-radius",
+        doc: "Largest eigenvalue modulus max |λ|.",
+        run: var_spectral_radius,
+    },
+];
+
+/// Resolves a var to its local name. Bare names are tolerated because the
+/// pod exposes a single namespace, so they cannot be ambiguous.
+fn var_name(var: &str) -> Option<&str> {
+    match var.split_once('/') {
+        Some((ns, name)) if ns == NAMESPACE => Some(name),
+        Some(_) => None,
+        None => {
+            eprintln!(
+                "pod-eigs: bare var {:?} resolved as {}/{}",
+                var, NAMESPACE, var
+            );
+            Some(var)
+        }
+    }
+}
+
+fn invoke_var(var: &str, input: &Value) -> Result<Value, String> {
+    let spec = var_name(var)
+        .and_then(|name| VARS.iter().find(|spec| spec.name == name))
+        .ok_or_else(|| "unknown var".to_string())?;
+    (spec.run)(input)
+}
+
+fn response_map(id: Option<Bencode>, pairs: Vec<(&str, Bencode)>) -> Bencode {
+    let mut dict = BTreeMap::new();
+    if let Some(id) = id {
+        dict.insert(b"id".to_vec(), id);
+    }
+    for (k, v) in pairs {
+        dict.insert(k.as_bytes().to_vec(), v);
+    }
+    Bencode::Dict(dict)
+}
+
+/// Payload formats this binary can speak. Optional formats must be gated
+/// with `#[cfg(feature = ...)]` here so `describe` never advertises one that
+/// was not compiled in.
+fn compiled_formats() -> Vec<&'static str> {
+    #[allow(unused_mut)]
+    let mut formats = vec!["json"];
+    #[cfg(feature = "edn")]
+    formats.push("edn");
+    formats
+}
+
+fn handle_describe(
+    id: Option<Bencode>,
+    session: &Session,
+    stdout: &mut dyn Write,
+) -> io::Result<()> {
+    let vars: Vec<Bencode> = VARS
+        .iter()
+        .map(|spec| {
+            Bencode::Dict(BTreeMap::from([
+                (
+                    b"name".to_vec(),
+                    Bencode::Bytes(spec.name.as_bytes().to_vec()),
+                ),
+                (
+                    b"doc".to_vec(),
+                    Bencode::Bytes(spec.doc.as_bytes().to_vec()),
+                ),
+                (b"arglists".to_vec(), Bencode::Bytes(b"([m])".to_vec())),
+            ]))
+        })
+        .collect();
+
+    let ns = Bencode::Dict(BTreeMap::from([
+        (
+            b"name".to_vec(),
+            Bencode::Bytes(NAMESPACE.as_bytes().to_vec()),
+        ),
+        (b"vars".to_vec(), Bencode::List(vars)),
+    ]));
+
+    let formats = compiled_formats()
+        .into_iter()
+        .map(|f| Bencode::Bytes(f.as_bytes().to_vec()))
+        .collect();
+
+    let resp = response_map(
+        id,
+        vec![
+            ("op", Bencode::Bytes(b"describe".to_vec())),
+            (
+                "format",
+                Bencode::Bytes(session.payload.name().as_bytes().to_vec()),
+            ),
+            ("formats", Bencode::List(formats)),
+            ("namespaces", Bencode::List(vec![ns])),
+        ],
+    );
+    write_response(&resp, stdout)
+}
+
+/// Writes the response that completes a request.
+fn write_response(resp: &Bencode, stdout: &mut dyn Write) -> io::Result<()> {
+    bump(&RESPONSES_OUT);
+    write_message(resp, stdout)
+}
+
+/// Encodes the whole message before writing so a failure can never leave a
+/// half-written message on the stream.
+fn write_message(msg: &Bencode, stdout: &mut dyn Write) -> io::Result<()> {
+    let encoded = encode_bencode(msg);
+    stdout.write_all(&encoded)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn done_map(id: Option<Bencode>) -> Bencode {
+    response_map(
+        id,
+        vec![(
+            "status",
+            Bencode::List(vec![Bencode::Bytes(b"done".to_vec())]),
+        )],
+    )
+}
+
+fn error_map(id: Option<Bencode>, msg: &str) -> Bencode {
+    response_map(
+        id,
+        vec![
+            ("op", Bencode::Bytes(b"invoke".to_vec())),
+            ("ex-message", Bencode::Bytes(msg.as_bytes().to_vec())),
+            ("ex-type", Bencode::Bytes(b"Exception".to_vec())),
+            (
+                "status",
+                Bencode::List(vec![
+                    Bencode::Bytes(b"done".to_vec()),
+                    Bencode::Bytes(b"error".to_vec()),
+                ]),
+            ),
+        ],
+    )
+}
+
+fn write_error(id: Option<Bencode>, msg: &str, stdout: &mut dyn Write) -> io::Result<()> {
+    write_response(&error_map(id, msg), stdout)
+}
+
+/// Native bencode rendering of a result for `value_format: "bencode"`.
+/// Integer-valued numbers become bencode ints, other numbers their decimal
+/// string, booleans 0/1 and null an empty list.
+fn json_to_bencode(v: &Value) -> Bencode {
+    match v {
+        Value::Null => Bencode::List(Vec::new()),
+        Value::Bool(b) => Bencode::Int(*b as i64),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                return Bencode::Int(i);
+            }
+            match n.as_f64() {
+                Some(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Bencode::Int(f as i64),
+                _ => Bencode::Bytes(n.to_string().into_bytes()),
+            }
+        }
+        Value::String(s) => Bencode::Bytes(s.as_bytes().to_vec()),
+        Value::Array(items) => Bencode::List(items.iter().map(json_to_bencode).collect()),
+        Value::Object(map) => Bencode::Dict(
+            map.iter()
+                .map(|(k, v)| (k.as_bytes().to_vec(), json_to_bencode(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Minimal EDN reader/writer for pod payloads. Reading maps EDN onto
+/// `serde_json::Value` (keywords become plain strings, vectors, lists and
+/// sets become arrays, ratios become floats); writing emits maps with
+/// keyword keys. Tags other than sets are rejected.
+#[cfg(feature = "edn")]
+mod edn {
+    use serde_json::{Map, Number, Value};
+
+    pub fn parse(text: &str) -> Result<Value, String> {
+        let mut reader = Reader {
+            bytes: text.as_bytes(),
+            pos: 0,
+        };
+        let value = reader.value(0)?;
+        reader.skip_ws();
+        if reader.pos != reader.bytes.len() {
+            return Err(format!("trailing input at byte {}", reader.pos));
+        }
+        Ok(value)
+    }
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+    }
+
+    impl Reader<'_> {
+        fn peek(&self) -> Option<u8> {
+            self.bytes.get(self.pos).copied()
+        }
+
+        fn skip_ws(&mut self) {
+            while let Some(b) = self.peek() {
+                match b {
+                    b' ' | b'\t' | b'\n' | b'\r' | b',' => self.pos += 1,
+                    b';' => {
+                        while !matches!(self.peek(), None | Some(b'\n')) {
+                            self.pos += 1;
+                        }
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        /// `depth` counts enclosing collections; like bencode input, EDN
+        /// deeper than `MAX_NESTING_DEPTH` is rejected rather than recursed.
+        fn value(&mut self, depth: usize) -> Result<Value, String> {
+            self.skip_ws();
+            let next = self.peek().ok_or("unexpected end of input")?;
+            if matches!(next, b'[' | b'(' | b'{' | b'#') && depth >= super::MAX_NESTING_DEPTH {
+                return Err("nesting too deep".to_string());
+            }
+            match next {
+                b'[' => self.seq(b']', depth),
+                b'(' => self.seq(b')', depth),
+                b'{' => self.map(depth),
+                b'#' if self.bytes.get(self.pos + 1) == Some(&b'{') => {
+                    self.pos += 1;
+                    self.seq(b'}', depth)
+                }
+                // Reading `#inst "..."` and friends as a symbol would
+                // silently corrupt the data, so other tags are rejected.
+                b'#' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    let tag = self.token().unwrap_or("");
+                    Err(format!("unsupported tag #{} at byte {}", tag, start))
+                }
+                b'"' => self.string().map(Value::String),
+                b':' => {
+                    self.pos += 1;
+                    Ok(Value::String(self.token()?.to_string()))
+                }
+                _ => self.atom(),
+            }
+        }
+
+        fn seq(&mut self, close: u8, depth: usize) -> Result<Value, String> {
+            self.pos += 1;
+            let mut items = Vec::new();
+            loop {
+                self.skip_ws();
+                match self.peek() {
+                    None => return Err("unterminated collection".to_string()),
+                    Some(b) if b == close => {
+                        self.pos += 1;
+                        return Ok(Value::Array(items));
+                    }
+                    Some(_) => items.push(self.value(depth + 1)?),
+                }
+            }
+        }
+
+        fn map(&mut self, depth: usize) -> Result<Value, String> {
+            self.pos += 1;
+            let mut map = Map::new();
+            loop {
+                self.skip_ws();
+                match self.peek() {
+                    None => return Err("unterminated map".to_string()),
+                    Some(b'}') => {
+                        self.pos += 1;
+                        return Ok(Value::Object(map));
+                    }
+                    Some(_) => {
+                        let key = match self.value(depth + 1)? {
+                            Value::String(k) => k,
+                            other => other.to_string(),
+                        };
+                        self.skip_ws();
+                        if self.peek() == Some(b'}') {
+                            return Err("map has an odd number of forms".to_string());
+                        }
+                        map.insert(key, self.value(depth + 1)?);
+                    }
+                }
+            }
+        }
+
+        fn string(&mut self) -> Result<String, String> {
+            self.pos += 1;
+            let mut out = Vec::new();
+            loop {
+                match self.peek().ok_or("unterminated string")? {
+                    b'"' => {
+                        self.pos += 1;
+                        return String::from_utf8(out)
+                            .map_err(|_| "invalid utf8 in string".to_string());
+                    }
+                    b'\\' => {
+                        self.pos += 1;
+                        let escaped = match self.peek().ok_or("unterminated string")? {
+                            b'n' => b'\n',
+                            b't' => b'\t',
+                            b'r' => b'\r',
+                            b'"' => b'"',
+                            b'\\' => b'\\',
+                            other => return Err(format!("unsupported escape \\{}", other as char)),
+                        };
+                        out.push(escaped);
+                        self.pos += 1;
+                    }
+                    b => {
+                        out.push(b);
+                        self.pos += 1;
+                    }
+                }
+            }
+        }
+
+        fn token(&mut self) -> Result<&str, String> {
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if matches!(
+                    b,
+                    b' ' | b'\t'
+                        | b'\n'
+                        | b'\r'
+                        | b','
+                        | b'['
+                        | b']'
+                        | b'('
+                        | b')'
+                        | b'{'
+                        | b'}'
+                        | b'"'
+                        | b';'
+                ) {
+                    break;
+                }
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return Err(format!("unexpected character at byte {}", start));
+            }
+            std::str::from_utf8(&self.bytes[start..self.pos])
+                .map_err(|_| "invalid utf8".to_string())
+        }
+
+        fn atom(&mut self) -> Result<Value, String> {
+            let token = self.token()?;
+            match token {
+                "nil" => return Ok(Value::Null),
+                "true" => return Ok(Value::Bool(true)),
+                "false" => return Ok(Value::Bool(false)),
+                _ => {}
+            }
+            if let Ok(i) = token.trim_end_matches('N').parse::<i64>() {
+                return Ok(Value::Number(i.into()));
+            }
+            let float = match token.split_once('/') {
+                Some((p, q)) => match (p.parse::<f64>(), q.parse::<f64>()) {
+                    (Ok(p), Ok(q)) if q != 0.0 => Some(p / q),
+                    _ => None,
+                },
+                None => token.trim_end_matches('M').parse::<f64>().ok(),
+            };
+            match float.and_then(Number::from_f64) {
+                Some(n) => Ok(Value::Number(n)),
+                None if token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+') => {
+                    Err(format!("invalid number {:?}", token))
+                }
+                // Bare symbols read as their name.
+                None => Ok(Value::String(token.to_string())),
+            }
+        }
+    }
+
+    fn is_keyword_name(key: &str) -> bool {
+        key.chars().next().is_some_and(|c| c.is_alphabetic())
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || "-_?!*.+<>=".contains(c))
+    }
+
+    pub fn write(value: &Value) -> String {
+        let mut out = String::new();
+        write_into(value, &mut out);
+        out
+    }
+
+    fn write_into(value: &Value, out: &mut String) {
+        match value {
+            Value::Null => out.push_str("nil"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            // serde_json already prints floats with a decimal point or exponent.
+            Value::Number(n) => out.push_str(&n.to_string()),
+            Value::String(s) => out.push_str(&Value::String(s.clone()).to_string()),
+            Value::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    write_into(item, out);
+                }
+                out.push(']');
+            }
+            Value::Object(map) => {
+                out.push('{');
+                for (i, (k, v)) in map.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    if is_keyword_name(k) {
+                        out.push(':');
+                        out.push_str(k);
+                    } else {
+                        write_into(&Value::String(k.clone()), out);
+                    }
+                    out.push(' ');
+                    write_into(v, out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Encoding of an invoke response's `value`: a string in the session's
+/// payload format, or native bencode.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum ValueFormat {
+    #[default]
+    Text,
+    Bencode,
+}
+
+/// Encoding of invoke `args` and text values, announced by `describe`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum PayloadFormat {
+    #[default]
+    Json,
+    #[cfg(feature = "edn")]
+    Edn,
+}
+
+impl PayloadFormat {
+    /// Only formats compiled into this binary parse.
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(PayloadFormat::Json),
+            #[cfg(feature = "edn")]
+            "edn" => Some(PayloadFormat::Edn),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PayloadFormat::Json => "json",
+            #[cfg(feature = "edn")]
+            PayloadFormat::Edn => "edn",
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> Result<Value, String> {
+        match self {
+            PayloadFormat::Json => {
+                serde_json::from_slice(bytes).map_err(|_| "invalid json input".to_string())
+            }
+            #[cfg(feature = "edn")]
+            PayloadFormat::Edn => std::str::from_utf8(bytes)
+                .map_err(|_| "invalid edn input".to_string())
+                .and_then(|text| edn::parse(text).map_err(|e| format!("invalid edn input: {}", e))),
+        }
+    }
+
+    fn encode(self, value: &Value) -> Result<String, String> {
+        match self {
+            PayloadFormat::Json => {
+                serde_json::to_string(value).map_err(|_| "failed to serialize output".to_string())
+            }
+            #[cfg(feature = "edn")]
+            PayloadFormat::Edn => Ok(edn::write(value)),
+        }
+    }
+}
+
+/// Payload format chosen at startup by `--format` or `POD_EIGS_FORMAT`.
+static DEFAULT_PAYLOAD: std::sync::OnceLock<PayloadFormat> = std::sync::OnceLock::new();
+
+/// Per-connection state negotiated from the `client` map of the first
+/// `describe`, e.g. `{"format" "bencode" "max_message_size" 65536}`.
+#[derive(Debug, Default)]
+struct Session {
+    negotiated: bool,
+    payload: PayloadFormat,
+    value_format: ValueFormat,
+    max_message_size: Option<usize>,
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            payload: DEFAULT_PAYLOAD.get().copied().unwrap_or_default(),
+            ..Session::default()
+        }
+    }
+
+    /// Maps a format name to a value encoding: the session's payload
+    /// format, or native bencode.
+    fn value_format(&self, name: &str) -> Option<ValueFormat> {
+        match name {
+            "bencode" => Some(ValueFormat::Bencode),
+            _ if name == self.payload.name() => Some(ValueFormat::Text),
+            _ => None,
+        }
+    }
+
+    fn negotiate(&mut self, dict: &BTreeMap<Vec<u8>, Bencode>) {
+        if self.negotiated {
+            return;
+        }
+        self.negotiated = true;
+        let client = match dict_get(dict, "client") {
+            Some(Bencode::Dict(client)) => client,
+            _ => return,
+        };
+        if let Some(format) = dict_get(client, "format").and_then(bencode_str) {
+            if format == "bencode" {
+                self.value_format = ValueFormat::Bencode;
+            } else if let Some(payload) = PayloadFormat::parse(&format) {
+                self.payload = payload;
+            } else {
+                eprintln!(
+                    "pod-eigs: client prefers unsupported format {:?}; using {}",
+                    format,
+                    self.payload.name()
+                );
+            }
+        }
+        if let Some(Bencode::Int(max)) = dict_get(client, "max_message_size") {
+            self.max_message_size = usize::try_from(*max).ok().filter(|m| *m > 0);
+        }
+    }
+}
+
+/// Batches at least this long are spread over the worker pool; smaller ones
+/// are not worth the hand-off.
+const PARALLEL_BATCH_MIN: usize = 8;
+
+/// Computes the `value` an invoke request answers with. Every failure is
+/// an `Err`, so `handle_invoke` has a single place that writes a response.
+fn invoke_value(dict: &BTreeMap<Vec<u8>, Bencode>, session: &Session) -> Result<Bencode, String> {
+    let var = dict_get(dict, "var")
+        .and_then(bencode_str)
+        .ok_or_else(|| "missing var".to_string())?;
+
+    let arg_bytes = match dict_get(dict, "args") {
+        Some(Bencode::List(items)) if !items.is_empty() => match &items[0] {
+            Bencode::Bytes(b) => Some(b.clone()),
+            _ => None,
+        },
+        Some(Bencode::Bytes(b)) => Some(b.clone()),
+        _ => None,
+    }
+    .ok_or_else(|| "missing args".to_string())?;
+
+    let json_input = session.payload.decode(&arg_bytes)?;
+
+    let json_input = match json_input {
+        Value::Array(mut items) if items.len() == 1 => items.remove(0),
+        Value::Array(_) => return Err("expected single arg map".to_string()),
+        other => other,
+    };
+
+    let format = match json_input.get("value_format") {
+        None => session.value_format,
+        Some(v) => v
+            .as_str()
+            .and_then(|name| session.value_format(name))
+            .ok_or_else(|| {
+                format!(
+                    "value_format must be \"{}\" or \"bencode\"",
+                    session.payload.name()
+                )
+            })?,
+    };
+
+    // A single arg that is itself a vector of maps is a batch: each map is
+    // computed independently and the results come back in input order.
+    let output = match &json_input {
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+            // The first map's `:batch_timeout_ms` bounds the whole batch; items
+            // not started in time are skipped, but the first always runs.
+            let deadline = batch_deadline(&items[0])?;
+            let started = Instant::now();
+            let run = |(i, item): (usize, &Value)| {
+                if deadline.is_some_and(|d| i > 0 && started.elapsed() >= d) {
+                    return None;
+                }
+                Some(invoke_var(&var, item).unwrap_or_else(|e| serde_json::json!({ "error": e })))
+            };
+            let slots: Vec<Option<Value>> = if items.len() >= PARALLEL_BATCH_MIN {
+                items.par_iter().enumerate().map(run).collect()
+            } else {
+                items.iter().enumerate().map(run).collect()
+            };
+            // Parallel workers can skip out of order; only the unbroken
+            // prefix is returned so results stay aligned with their inputs.
+            let results: Vec<Value> = slots.into_iter().map_while(|r| r).collect();
+            let mut out = serde_json::json!({ "results": results });
+            if deadline.is_some() {
+                out["remaining"] = serde_json::json!(items.len() - results.len());
+            }
+            out
+        }
+        _ => invoke_var(&var, &json_input)?,
+    };
+
+    match format {
+        ValueFormat::Bencode => Ok(json_to_bencode(&output)),
+        ValueFormat::Text => session
+            .payload
+            .encode(&output)
+            .map(|s| Bencode::Bytes(s.into_bytes())),
+    }
+}
+
+fn handle_invoke(
+    dict: &BTreeMap<Vec<u8>, Bencode>,
+    session: &Session,
+    stdout: &mut dyn Write,
+) -> io::Result<()> {
+    let id = dict_get(dict, "id").cloned();
+    INVOKE_COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+    let result = invoke_value(dict, session).and_then(|value| {
+        let resp = response_map(
+            id.clone(),
+            vec![("op", Bencode::Bytes(b"invoke".to_vec())), ("value", value)],
+        );
+        if let Some(max) = session.max_message_size {
+            let size = encode_bencode(&resp).len();
+            if size > max {
+                return Err(format!(
+                    "response of {} bytes exceeds client max_message_size {}",
+                    size, max
+                ));
+            }
+        }
+        Ok(resp)
+    });
+    let resp = match result {
+        Ok(resp) => resp,
+        Err(msg) => {
+            INVOKE_ERROR_COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+            record_error(&msg);
+            return write_error(id, &msg, stdout);
+        }
+    };
+    // A value is followed by a separate `done` status, which is what tells
+    // the host the call is complete; an error carries `done` itself.
+    write_message(&resp, stdout)?;
+    write_response(&done_map(id), stdout)
+}
+
+/// Answers the pod-specific `stats` op with the process-wide counters.
+/// Standard hosts never send it.
+fn stats_map(id: Option<Bencode>) -> Bencode {
+    let load = |c: &AtomicUsize| c.load(AtomicOrdering::Relaxed) as i64;
+    let uptime = STARTED.get_or_init(Instant::now).elapsed().as_millis() as i64;
+    let mut pairs = vec![
+        ("uptime_ms", Bencode::Int(uptime)),
+        ("messages", Bencode::Int(load(&MESSAGE_COUNT))),
+        ("parse_errors", Bencode::Int(load(&PARSE_ERROR_COUNT))),
+        ("invokes", Bencode::Int(load(&INVOKE_COUNT))),
+        ("invoke_errors", Bencode::Int(load(&INVOKE_ERROR_COUNT))),
+        (
+            "errors",
+            Bencode::Int(load(&PARSE_ERROR_COUNT) + load(&INVOKE_ERROR_COUNT)),
+        ),
+        ("peak_buffer_bytes", Bencode::Int(load(&PEAK_BUFFER))),
+        (
+            "status",
+            Bencode::List(vec![Bencode::Bytes(b"done".to_vec())]),
+        ),
+    ];
+    if let Some(last) = LAST_ERROR.lock().ok().and_then(|last| last.clone()) {
+        pairs.push(("last_error", Bencode::Bytes(last.into_bytes())));
+    }
+    response_map(id, pairs)
+}
+
+/// What the message loop should do after a message has been answered.
+#[derive(Debug, PartialEq)]
+enum Flow {
+    Continue,
+    Shutdown,
+}
+
+/// Answers one message. This is the only place ops are dispatched, so the
+/// loop learns about `shutdown` from the returned `Flow` rather than by
+/// inspecting the message itself.
+fn handle_message(msg: Bencode, session: &mut Session, stdout: &mut dyn Write) -> io::Result<Flow> {
+    MESSAGE_COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+    let dict = match msg {
+        Bencode::Dict(d) => d,
+        other => {
+            // There is no id to echo, but an uncorrelated error still beats
+            // leaving the host waiting on a message we silently dropped.
+            eprintln!(
+                "pod-eigs: expected a dict message at top level, got {:?}",
+                other
+            );
+            bump(&REQUESTS_IN);
+            return write_error(None, "expected a dict message at top level", stdout)
+                .map(|_| Flow::Continue);
+        }
+    };
+    let op = dict_get(&dict, "op")
+        .and_then(bencode_str)
+        .unwrap_or_default();
+    let id = dict_get(&dict, "id").cloned();
+
+    match op.as_str() {
+        "describe" => {
+            bump(&REQUESTS_IN);
+            session.negotiate(&dict);
+            handle_describe(id, session, stdout)?;
+        }
+        "invoke" => {
+            bump(&REQUESTS_IN);
+            handle_invoke(&dict, session, stdout)?;
+        }
+        "shutdown" => {
+            bump(&REQUESTS_IN);
+            // Some hosts wait for a reply before closing their end.
+            write_response(&done_map(id), stdout)?;
+            return Ok(Flow::Shutdown);
+        }
+        "stats" => {
+            bump(&REQUESTS_IN);
+            write_response(&stats_map(id), stdout)?;
+        }
+        "ping" => {
+            bump(&REQUESTS_IN);
+            let pong = response_map(
+                id,
+                vec![
+                    ("op", Bencode::Bytes(b"ping".to_vec())),
+                    (
+                        "status",
+                        Bencode::List(vec![Bencode::Bytes(b"done".to_vec())]),
+                    ),
+                ],
+            );
+            write_response(&pong, stdout)?;
+        }
+        _ => {}
+    }
+    Ok(Flow::Continue)
+}
+
+/// Parses `POD_EIGS_THREADS`; `None` keeps rayon's default pool size.
+fn thread_count(raw: Option<&str>) -> Result<Option<usize>, String> {
+    match raw {
+        None => Ok(None),
+        Some(s) => match s.trim().parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Some(n)),
+            _ => Err(format!(
+                "POD_EIGS_THREADS must be a positive integer, got {:?}",
+                s
+            )),
+        },
+    }
+}
+
+fn main() -> io::Result<()> {
+    let threads =
+        thread_count(std::env::var("POD_EIGS_THREADS").ok().as_deref()).unwrap_or_else(|e| {
+            eprintln!("pod-eigs: {}; using the default thread count", e);
+            None
+        });
+    if let Some(threads) = threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+        {
+            eprintln!(
+                "pod-eigs: could not start {} worker threads: {}",
+                threads, e
+            );
+        }
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let payload = payload_format(&args, std::env::var("POD_EIGS_FORMAT").ok().as_deref())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let _ = DEFAULT_PAYLOAD.set(payload);
+    let strict = std::env::var("POD_EIGS_STRICT_BENCODE").is_ok_and(|v| v == "1" || v == "true");
+    STRICT_BENCODE.store(strict, AtomicOrdering::Relaxed);
+    match socket_port(
+        &args,
+        std::env::var("BABASHKA_POD_TRANSPORT").ok().as_deref(),
+    ) {
+        Ok(Some(port)) => serve_socket(port),
+        Ok(None) => serve(&mut io::stdin(), &mut io::stdout()).map(|_| ()),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidInput, e)),
+    }
+}
+
+/// Picks the payload format: `--format <name>`, else `POD_EIGS_FORMAT`,
+/// else json.
+fn payload_format(args: &[String], env: Option<&str>) -> Result<PayloadFormat, String> {
+    let name = match args.iter().position(|a| a == "--format") {
+        Some(i) => args
+            .get(i + 1)
+            .map(String::as_str)
+            .ok_or("--format requires a format name")?,
+        None => match env {
+            Some(name) => name,
+            None => return Ok(PayloadFormat::default()),
+        },
+    };
+    PayloadFormat::parse(name).ok_or_else(|| {
+        format!(
+            "unsupported format {:?}; this build speaks {}",
+            name,
+            compiled_formats().join(", ")
+        )
+    })
+}
+
+/// Picks the transport: `--socket <port>` binds that port, while
+/// `BABASHKA_POD_TRANSPORT=socket` (set by babashka itself) binds an
+/// ephemeral port. `None` means the default stdin/stdout transport.
+fn socket_port(args: &[String], transport: Option<&str>) -> Result<Option<u16>, String> {
+    match args.iter().position(|a| a == "--socket") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|p| p.parse::<u16>().ok())
+            .map(Some)
+            .ok_or_else(|| "--socket requires a port number".to_string()),
+        None if transport == Some("socket") => Ok(Some(0)),
+        None => Ok(None),
+    }
+}
+
+fn serve_socket(port: u16) -> io::Result<()> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+    let bound = listener.local_addr()?.port();
+    // babashka looks for the chosen port in `.babashka-pod-<pid>.port`.
+    let port_file = port_file_name();
+    std::fs::write(&port_file, format!("{}\n", bound))?;
+    eprintln!("pod-eigs: listening on 127.0.0.1:{}", bound);
+    let result = accept_loop(&listener);
+    // Removed on shutdown and when accepting fails alike.
+    if let Err(e) = std::fs::remove_file(&port_file) {
+        eprintln!("pod-eigs: could not remove {}: {}", port_file, e);
+    }
+    result
+}
+
+fn port_file_name() -> String {
+    format!(".babashka-pod-{}.port", std::process::id())
+}
+
+/// Serves one connection at a time, accepting the next client whenever the
+/// current one disconnects. Only a `shutdown` message ends the loop.
+fn accept_loop(listener: &std::net::TcpListener) -> io::Result<()> {
+    loop {
+        let (mut stream, peer) = listener.accept()?;
+        let mut reader = stream.try_clone()?;
+        match serve(&mut reader, &mut stream) {
+            Ok(SessionEnd::Shutdown) => return Ok(()),
+            Ok(_) => eprintln!("pod-eigs: {} disconnected", peer),
+            Err(e) => eprintln!("pod-eigs: connection from {} failed: {}", peer, e),
+        }
+    }
+}
+
+/// Why `serve` stopped reading.
+#[derive(Debug, PartialEq)]
+enum SessionEnd {
+    Eof,
+    Shutdown,
+    Hangup,
+}
+
+/// Runs the bencode message loop until EOF, shutdown, or the host hangs up.
+fn serve(input: &mut dyn Read, output: &mut dyn Write) -> io::Result<SessionEnd> {
+    let mut buffer: Vec<u8> = Vec::new();
+    // Bytes before `start` are consumed. They are compacted away only once
+    // they make up half the buffer, so each message costs O(1) amortized
+    // instead of shifting the whole backlog.
+    let mut start = 0;
+    let mut chunk = [0u8; 4096];
+    let mut session = Session::new();
+    STARTED.get_or_init(Instant::now);
+
+    loop {
+        let n = input.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        if start == buffer.len() {
+            buffer.clear();
+            start = 0;
+        } else if start > buffer.len() / 2 {
+            buffer.drain(..start);
+            start = 0;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        PEAK_BUFFER.fetch_max(buffer.len() - start, AtomicOrdering::Relaxed);
+
+        loop {
+            match parse_at(&buffer, start) {
+                Ok((msg, end)) => {
+                    start = end;
+                    let flow = match handle_message(msg, &mut session, output) {
+                        Ok(flow) => flow,
+                        // The host closed its end; nobody is left to answer.
+                        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                            eprintln!("pod-eigs: output closed, exiting");
+                            return Ok(SessionEnd::Hangup);
+                        }
+                        Err(e) => return Err(e),
+                    };
+                    debug_assert_eq!(
+                        REQUESTS_IN.with(Cell::get),
+                        RESPONSES_OUT.with(Cell::get),
+                        "every request must get exactly one response"
+                    );
+                    if flow == Flow::Shutdown {
+                        return Ok(SessionEnd::Shutdown);
+                    }
+                }
+                Err(ParseError::NeedMore) => break,
+                Err(ParseError::Invalid(msg)) => {
+                    // Recover by dropping one byte and retrying parse. Clearing the
+                    // whole buffer can desynchronize request/response matching and
+                    // leave the host waiting forever.
+                    let n = PARSE_ERROR_COUNT.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+                    record_error(&msg);
+                    eprintln!(
+                        "pod-eigs parse error #{}: {} (buffer-len={})",
+                        n,
+                        msg,
+                        buffer.len() - start
+                    );
+                    if start < buffer.len() {
+                        start += 1;
+                        continue;
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(SessionEnd::Eof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close_enough(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1.0e-6
+    }
+
+    fn symmetric_opts() -> EigOptions {
+        EigOptions {
+            symmetric: true,
+            ..EigOptions::default()
+        }
+    }
+
+    #[test]
+    fn identity_matrix() {
+        let mut data = [0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = 1.0;
+        }
+        let matrix = DMatrix::from_row_slice(6, 6, &data);
+        let out = eigenvalues_for(matrix, &symmetric_opts()).unwrap();
+        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
+        assert_eq!(vals.len(), 6);
+        for v in vals {
+            assert!(close_enough(v.as_f64().unwrap(), 1.0));
+        }
+    }
+
+    #[test]
+    fn zero_matrix() {
+        let data = [0.0f64; 36];
+        let matrix = DMatrix::from_row_slice(6, 6, &data);
+        let out = eigenvalues_for(matrix, &symmetric_opts()).unwrap();
+        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
+        assert_eq!(vals.len(), 6);
+        for v in vals {
             assert!(close_enough(v.as_f64().unwrap(), 0.0));
         }
+
+        // nalgebra's Schur cannot converge on a zero matrix of dimension 3+.
+        for n in [2, 3, 6] {
+            let arg = serde_json::json!({ "rows": vec![vec![0.0; n]; n], "with_schur": true });
+            let out = invoke_var("pod.eigs/eigenvalues", &arg).unwrap();
+            assert_eq!(out["eigenvalues"], serde_json::json!(vec![[0.0, 0.0]; n]));
+            assert_eq!(out["schur"], serde_json::json!(vec![vec![0.0; n]; n]));
+        }
+    }
+
+    #[test]
+    fn symmetric_block() {
+        let mut data = [0.0f64; 36];
+        data[0] = 2.0;
+        data[1] = 1.0;
+        data[6] = 1.0;
+        data[7] = 2.0;
+        let matrix = DMatrix::from_row_slice(6, 6, &data);
+        let out = eigenvalues_for(matrix, &symmetric_opts()).unwrap();
+        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
+        let mut nums: Vec<f64> = vals.iter().map(|v| v.as_f64().unwrap()).collect();
+        nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        assert_eq!(nums.len(), 6);
+        assert!(close_enough(nums[5], 3.0));
+        assert!(close_enough(nums[4], 1.0));
+    }
+
+    #[test]
+    fn direct_sum_spectrum_is_union() {
+        let input = serde_json::json!({
+            "a": [[2.0, 1.0], [1.0, 2.0]],
+            "b": [[5.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 4.0]]
+        });
+        let out = invoke_var("pod.eigs/dsum", &input).unwrap();
+        let sum = parse_rows(out.get("dsum").unwrap(), "dsum").unwrap();
+        assert_eq!(sum.shape(), (5, 5));
+        assert_eq!(sum[(0, 2)], 0.0);
+        assert_eq!(sum[(3, 1)], 0.0);
+        let vals = symmetric_eigenvalues(sum);
+        let expected = [-1.0, 1.0, 3.0, 4.0, 5.0];
+        for (v, e) in vals.iter().zip(expected.iter()) {
+            assert!(close_enough(*v, *e));
+        }
+    }
+
+    #[test]
+    fn error_bounds_cover_true_error() {
+        // Tridiagonal (-1, 2, -1) has eigenvalues 2 - 2cos(k*pi/7), k = 1..6.
+        let mut rows = vec![vec![0.0; 6]; 6];
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[i] = 2.0;
+            if i > 0 {
+                row[i - 1] = -1.0;
+            }
+            if i < 5 {
+                row[i + 1] = -1.0;
+            }
+        }
+        let input =
+            serde_json::json!({ "rows": rows, "symmetric": true, "with_error_bounds": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
+        let bounds = out.get("error_bounds").unwrap().as_array().unwrap();
+        assert_eq!(bounds.len(), 6);
+        for (k, (v, b)) in vals.iter().zip(bounds.iter()).enumerate() {
+            let exact = 2.0 - 2.0 * ((k + 1) as f64 * std::f64::consts::PI / 7.0).cos();
+            let bound = b.as_f64().unwrap();
+            assert!(bound > 0.0 && bound < 1.0e-10);
+            assert!((v.as_f64().unwrap() - exact).abs() <= bound);
+        }
+    }
+
+    #[test]
+    fn mean_of_two_matrices() {
+        let input = serde_json::json!({
+            "matrices": [[[1.0, 2.0], [2.0, 1.0]], [[3.0, 0.0], [0.0, 3.0]]],
+            "with_eigenvalues": true,
+            "symmetric": true
+        });
+        let out = invoke_var("pod.eigs/mean", &input).unwrap();
+        assert_eq!(out["mean"], serde_json::json!([[2.0, 1.0], [1.0, 2.0]]));
+        let vals = out["eigenvalues"].as_array().unwrap();
+        assert!(close_enough(vals[0].as_f64().unwrap(), 1.0));
+        assert!(close_enough(vals[1].as_f64().unwrap(), 3.0));
+
+        let mismatched = serde_json::json!({ "matrices": [[[1.0]], [[1.0, 0.0], [0.0, 1.0]]] });
+        assert!(invoke_var("pod.eigs/mean", &mismatched).is_err());
+    }
+
+    fn decode_all(mut bytes: &[u8]) -> Vec<BTreeMap<Vec<u8>, Bencode>> {
+        let mut out = Vec::new();
+        while !bytes.is_empty() {
+            let (msg, used) = parse_at(bytes, 0).unwrap();
+            match msg {
+                Bencode::Dict(d) => out.push(d),
+                other => panic!("expected dict, got {:?}", other),
+            }
+            bytes = &bytes[used..];
+        }
+        out
+    }
+
+    #[test]
+    fn describe_lists_only_compiled_formats() {
+        let mut out = Vec::new();
+        handle_describe(None, &Session::default(), &mut out).unwrap();
+        let msgs = decode_all(&out);
+        assert_eq!(msgs.len(), 1);
+        let formats = match dict_get(&msgs[0], "formats") {
+            Some(Bencode::List(items)) => items.iter().filter_map(bencode_str).collect::<Vec<_>>(),
+            other => panic!("missing formats: {:?}", other),
+        };
+        assert_eq!(formats, compiled_formats());
+        assert_eq!(
+            dict_get(&msgs[0], "format")
+                .and_then(bencode_str)
+                .as_deref(),
+            Some("json")
+        );
+    }
+
+    #[test]
+    fn maximally_mixed_state_entropy() {
+        let mut data = vec![0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = 1.0 / 6.0;
+        }
+        let out = invoke_var(
+            "pod.eigs/density-eigs",
+            &serde_json::json!({ "data": data }),
+        )
+        .unwrap();
+        assert!(close_enough(out["entropy"].as_f64().unwrap(), 6.0f64.ln()));
+        assert!(out.get("warning").is_none());
+        for p in out["probabilities"].as_array().unwrap() {
+            assert!(close_enough(p.as_f64().unwrap(), 1.0 / 6.0));
+        }
+
+        // An unnormalized identity is rescaled with a warning.
+        let scaled: Vec<f64> = data.iter().map(|x| x * 12.0).collect();
+        let out = invoke_var(
+            "pod.eigs/density-eigs",
+            &serde_json::json!({ "data": scaled }),
+        )
+        .unwrap();
+        assert!(out["warning"].as_str().unwrap().contains("normalized"));
+        assert!(close_enough(out["entropy"].as_f64().unwrap(), 6.0f64.ln()));
+    }
+
+    #[test]
+    fn uniform_eigenvector_ipr_is_one_over_n() {
+        // The all-ones matrix has top eigenvector (1, ..., 1) / sqrt(6).
+        let input =
+            serde_json::json!({ "data": vec![1.0; 36], "symmetric": true, "with_ipr": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        let vals = out["eigenvalues"].as_array().unwrap();
+        let ipr = out["ipr"].as_array().unwrap();
+        assert_eq!(ipr.len(), 6);
+        assert!(close_enough(vals[5].as_f64().unwrap(), 6.0));
+        assert!(close_enough(ipr[5].as_f64().unwrap(), 1.0 / 6.0));
+
+        let mut data = vec![0.0; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = i as f64;
+        }
+        let input = serde_json::json!({ "data": data, "symmetric": true, "with_ipr": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        for v in out["ipr"].as_array().unwrap() {
+            assert!(close_enough(v.as_f64().unwrap(), 1.0));
+        }
+    }
+
+    #[test]
+    fn trace_exp_matches_matrix_exponential() {
+        let mut data = [0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = 0.5 * i as f64;
+            if i < 5 {
+                data[i * 6 + i + 1] = 0.3;
+                data[(i + 1) * 6 + i] = 0.3;
+            }
+        }
+        let beta = 0.7;
+        let input = serde_json::json!({ "data": data.to_vec(), "beta": beta });
+        let out = invoke_var("pod.eigs/trace-exp", &input).unwrap();
+        let expected = (DMatrix::from_row_slice(6, 6, &data) * -beta).exp().trace();
+        assert!(close_enough(out["trace_exp"].as_f64().unwrap(), expected));
+
+        let missing = serde_json::json!({ "data": data.to_vec() });
+        assert!(invoke_var("pod.eigs/trace-exp", &missing).is_err());
+    }
+
+    #[test]
+    fn meta_fields_on_success() {
+        let data: Vec<f64> = (0..36)
+            .map(|i| if i % 7 == 0 { 1.0 + i as f64 } else { 0.0 })
+            .collect();
+        let input = serde_json::json!({ "data": data, "with_meta": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        let meta = &out["meta"];
+        assert!(meta["elapsed_us"].as_u64().is_some());
+        assert_eq!(meta["path"], "general");
+        assert_eq!(meta["dim"], 6);
+
+        let plain =
+            invoke_var("pod.eigs/eigenvalues", &serde_json::json!({ "data": data })).unwrap();
+        assert!(plain.get("meta").is_none());
+    }
+
+    #[test]
+    fn care_double_integrator() {
+        // Double integrator with Q = I, R = 1 has X = [[sqrt 3, 1], [1, sqrt 3]].
+        let input = serde_json::json!({
+            "a": [[0.0, 1.0], [0.0, 0.0]],
+            "b": [[0.0], [1.0]],
+            "q": [[1.0, 0.0], [0.0, 1.0]],
+            "r": [[1.0]]
+        });
+        let out = invoke_var("pod.eigs/care", &input).unwrap();
+        let x = parse_rows(&out["x"], "x").unwrap();
+        let s3 = 3.0f64.sqrt();
+        assert!(close_enough(x[(0, 0)], s3));
+        assert!(close_enough(x[(0, 1)], 1.0));
+        assert!(close_enough(x[(1, 0)], 1.0));
+        assert!(close_enough(x[(1, 1)], s3));
+        assert!(out["residual"].as_f64().unwrap() < 1.0e-8);
+
+        let bad = serde_json::json!({
+            "a": [[0.0, 1.0], [0.0, 0.0]],
+            "b": [[0.0], [1.0]],
+            "q": [[1.0, 0.0], [0.0, 1.0]],
+            "r": [[-1.0]]
+        });
+        assert!(invoke_var("pod.eigs/care", &bad).is_err());
+    }
+
+    #[test]
+    fn care_scaling_survives_huge_determinants() {
+        // A = 1000 I, B = R = I, Q = I in 60 dimensions: det(H) = -(1e6 + 1)^60
+        // overflows f64, yet X = (a + sqrt(a^2 + 1)) I is well defined.
+        let n = 60;
+        let diag = |v: f64| matrix_value(&DMatrix::from_diagonal_element(n, n, v), Layout::Row);
+        let input = serde_json::json!({ "a": diag(1000.0), "b": diag(1.0), "q": diag(1.0), "r": diag(1.0) });
+        let out = invoke_var("pod.eigs/care", &input).unwrap();
+        let x = parse_rows(&out["x"], "x").unwrap();
+        let expected = DMatrix::from_diagonal_element(n, n, 1000.0 + 1.000001e6f64.sqrt());
+        assert!((x - expected).norm() < 1.0e-6);
+    }
+
+    #[test]
+    fn read_upper_triangle_ignores_lower() {
+        let mut rows = vec![vec![0.0; 6]; 6];
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[i] = 2.0;
+        }
+        rows[0][1] = 1.0;
+        rows[1][0] = 7.0;
+        let both = serde_json::json!({ "rows": rows, "symmetric": true });
+        assert!(invoke_var("pod.eigs/eigenvalues", &both).is_err());
+
+        let upper =
+            serde_json::json!({ "rows": rows, "symmetric": true, "read_triangle": "upper" });
+        let out = invoke_var("pod.eigs/eigenvalues", &upper).unwrap();
+        let vals = out["eigenvalues"].as_array().unwrap();
+        assert!(close_enough(vals[0].as_f64().unwrap(), 1.0));
+        assert!(close_enough(vals[5].as_f64().unwrap(), 3.0));
+
+        let bad =
+            serde_json::json!({ "rows": rows, "symmetric": true, "read_triangle": "diagonal" });
+        assert!(invoke_var("pod.eigs/eigenvalues", &bad).is_err());
+    }
+
+    #[test]
+    fn lanczos_extreme_ritz_values() {
+        let mut data = vec![0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = (i * i) as f64;
+            if i < 5 {
+                data[i * 6 + i + 1] = 0.5;
+                data[(i + 1) * 6 + i] = 0.5;
+            }
+        }
+        let exact = symmetric_eigenvalues(DMatrix::from_row_slice(6, 6, &data));
+
+        let full = invoke_var(
+            "pod.eigs/lanczos",
+            &serde_json::json!({ "data": data, "k": 6 }),
+        )
+        .unwrap();
+        assert!(close_enough(full["largest"].as_f64().unwrap(), exact[5]));
+        assert!(close_enough(full["smallest"].as_f64().unwrap(), exact[0]));
+
+        let partial = invoke_var(
+            "pod.eigs/lanczos",
+            &serde_json::json!({ "data": data, "k": 4 }),
+        )
+        .unwrap();
+        let largest = partial["largest"].as_f64().unwrap();
+        assert!(largest <= exact[5] + 1.0e-9);
+        assert!(
+            (largest - exact[5]).abs()
+                <= partial["residuals"]["largest"].as_f64().unwrap() + 1.0e-9
+        );
+        assert_eq!(partial["steps"], 4);
+
+        assert!(invoke_var(
+            "pod.eigs/lanczos",
+            &serde_json::json!({ "data": data, "k": 7 })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn top_level_list_gets_error_response() {
+        let mut out = Vec::new();
+        handle_message(
+            Bencode::List(vec![Bencode::Int(1)]),
+            &mut Session::default(),
+            &mut out,
+        )
+        .unwrap();
+        let msgs = decode_all(&out);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            dict_get(&msgs[0], "ex-message")
+                .and_then(bencode_str)
+                .as_deref(),
+            Some("expected a dict message at top level")
+        );
+        assert!(dict_get(&msgs[0], "id").is_none());
+    }
+
+    #[test]
+    fn spd_geometric_mean_identities() {
+        let a = serde_json::json!([[4.0, 1.0, 0.0], [1.0, 3.0, 0.5], [0.0, 0.5, 2.0]]);
+        let a_mat = parse_rows(&a, "a").unwrap();
+        let eye = serde_json::json!([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+
+        let out = invoke_var(
+            "pod.eigs/spd-geometric-mean",
+            &serde_json::json!({ "a": a, "b": a }),
+        )
+        .unwrap();
+        let mean = parse_rows(&out["mean"], "mean").unwrap();
+        assert!((mean - &a_mat).norm() < 1.0e-9);
+
+        let out = invoke_var(
+            "pod.eigs/spd-geometric-mean",
+            &serde_json::json!({ "a": a, "b": eye }),
+        )
+        .unwrap();
+        let mean = parse_rows(&out["mean"], "mean").unwrap();
+        assert!((&mean * &mean - &a_mat).norm() < 1.0e-9);
+
+        let indefinite = serde_json::json!([[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert!(invoke_var(
+            "pod.eigs/spd-geometric-mean",
+            &serde_json::json!({ "a": a, "b": indefinite })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn spectrum_centroid_matches_trace() {
+        let mut data = [0.0f64; 36];
+        data[1] = -2.0;
+        data[6] = 2.0;
+        data[0] = 0.5;
+        data[7] = 0.5;
+        for i in 2..6 {
+            data[i * 6 + i] = i as f64;
+        }
+        data[2 * 6 + 3] = 1.0;
+        let out = invoke_var(
+            "pod.eigs/spectrum-centroid",
+            &serde_json::json!({ "data": data.to_vec() }),
+        )
+        .unwrap();
+        let trace = DMatrix::from_row_slice(6, 6, &data).trace();
+        let centroid = out["centroid"].as_array().unwrap();
+        assert!(close_enough(centroid[0].as_f64().unwrap(), trace / 6.0));
+        assert!(close_enough(centroid[1].as_f64().unwrap(), 0.0));
+
+        let eig = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "data": data.to_vec() }),
+        )
+        .unwrap();
+        assert!(eig["eigenvalues"].as_array().unwrap().iter().any(|p| p[1]
+            .as_f64()
+            .unwrap()
+            .abs()
+            > 1.0));
+    }
+
+    #[test]
+    fn relative_eps_scales_with_norm() {
+        let data: Vec<f64> = (0..36)
+            .map(|k| 1.0e8 * (((k / 6) * 7 + (k % 6) * 3) % 11) as f64 - 5.0e8)
+            .collect();
+        let norm = DMatrix::from_row_slice(6, 6, &data).norm();
+        // With a budget of 8 sweeps the absolute 1e-12 cannot be met on a
+        // matrix of norm ~1e9, while the norm-relative tolerance can.
+        let absolute = serde_json::json!({ "data": data, "max_iter": 8 });
+        let err = invoke_var("pod.eigs/eigenvalues", &absolute).unwrap_err();
+        assert!(
+            err.contains("failed to converge within 8 iterations"),
+            "{}",
+            err
+        );
+
+        let input = serde_json::json!({ "data": data, "max_iter": 8, "with_meta": true, "relative_eps": true });
+        let relative = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        let eps = relative["meta"]["epsilon"].as_f64().unwrap();
+        assert!((eps - SCHUR_EPS * norm).abs() <= 1.0e-6 * eps);
+        let reference =
+            invoke_var("pod.eigs/eigenvalues", &serde_json::json!({ "data": data })).unwrap();
+        let vals: Vec<[f64; 2]> = serde_json::from_value(relative["eigenvalues"].clone()).unwrap();
+        let want: Vec<[f64; 2]> = serde_json::from_value(reference["eigenvalues"].clone()).unwrap();
+        for (got, want) in vals.iter().zip(&want) {
+            assert!((got[0] - want[0]).abs() + (got[1] - want[1]).abs() < 1.0e-3 * norm);
+        }
+
+        // Under :balance the tolerance follows the balanced matrix.
+        let skewed = DMatrix::from_row_slice(
+            3,
+            3,
+            &[1.0, 1.0e4, 0.0, 1.0e-4, 1.0, 1.0e4, 0.0, 1.0e-4, 1.0],
+        );
+        let balanced_norm = balance_matrix(&skewed).0.norm();
+        assert!(balanced_norm < 1.0e-2 * skewed.norm());
+        let arg = serde_json::json!({ "rows": matrix_value(&skewed, Layout::Row), "with_meta": true, "relative_eps": true, "balance": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &arg).unwrap();
+        let eps = out["meta"]["epsilon"].as_f64().unwrap();
+        assert!((eps - SCHUR_EPS * balanced_norm).abs() <= 1.0e-6 * eps);
+    }
+
+    #[test]
+    fn perron_pair_of_positive_matrix() {
+        let data: Vec<f64> = (0..36).map(|i| 1.0 + (i % 5) as f64).collect();
+        let out = invoke_var("pod.eigs/perron", &serde_json::json!({ "data": data })).unwrap();
+        assert_eq!(out["converged"], true);
+        let root = out["eigenvalue"].as_f64().unwrap();
+        assert!(root > 0.0);
+        let matrix = DMatrix::from_row_slice(6, 6, &data);
+        let spectrum = general_eigenvalues(matrix.clone()).unwrap();
+        let rho = spectrum.iter().map(|c| c.norm()).fold(0.0, f64::max);
+        assert!(close_enough(root, rho));
+        let v: Vec<f64> = out["eigenvector"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_f64().unwrap())
+            .collect();
+        assert!(v.iter().all(|&x| x > 0.0));
+        let v = DVector::from_vec(v);
+        assert!((&matrix * &v - &v * root).norm() < 1.0e-9);
+
+        let mut negative = data.clone();
+        negative[7] = -1.0;
+        let err =
+            invoke_var("pod.eigs/perron", &serde_json::json!({ "data": negative })).unwrap_err();
+        assert!(err.contains("(1, 1)"));
+    }
+
+    #[test]
+    fn group_by_sign_matches_signature() {
+        let diag = [-3.0, -1.0, 0.0, 2.0, 5.0, 7.0];
+        let mut data = vec![0.0f64; 36];
+        for (i, d) in diag.iter().enumerate() {
+            data[i * 6 + i] = *d;
+        }
+        let input = serde_json::json!({ "data": data, "symmetric": true, "group_by_sign": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        let groups = &out["eigenvalues"];
+        assert_eq!(groups["negative"].as_array().unwrap().len(), 2);
+        assert_eq!(groups["zero"].as_array().unwrap().len(), 1);
+        assert_eq!(groups["positive"].as_array().unwrap().len(), 3);
+        assert!(close_enough(groups["positive"][2].as_f64().unwrap(), 7.0));
+    }
+
+    #[test]
+    fn coherence_orthonormal_and_parallel() {
+        let mut eye = vec![0.0f64; 36];
+        for i in 0..6 {
+            eye[i * 6 + i] = 1.0;
+        }
+        let out = invoke_var("pod.eigs/coherence", &serde_json::json!({ "data": eye })).unwrap();
+        assert!(close_enough(out["coherence"].as_f64().unwrap(), 0.0));
+
+        let mut near = eye.clone();
+        // Column 4 becomes nearly parallel to column 5.
+        near[4 * 6 + 4] = 0.0;
+        near[5 * 6 + 4] = 1.0;
+        near[0] = 1.0;
+        near[4] = 1.0e-3;
+        let out = invoke_var("pod.eigs/coherence", &serde_json::json!({ "data": near })).unwrap();
+        assert!(out["coherence"].as_f64().unwrap() > 0.999);
+        assert_eq!(out["columns"], serde_json::json!([4, 5]));
+
+        let mut zero_col = eye.clone();
+        zero_col[2 * 6 + 2] = 0.0;
+        assert!(invoke_var(
+            "pod.eigs/coherence",
+            &serde_json::json!({ "data": zero_col })
+        )
+        .is_err());
+
+        // An overcomplete 2x3 dictionary: the third atom bisects the first two.
+        let wide = serde_json::json!([[1.0, 0.0, 1.0], [0.0, 1.0, 1.0]]);
+        let out = invoke_var("pod.eigs/coherence", &serde_json::json!({ "rows": wide })).unwrap();
+        assert!(close_enough(
+            out["coherence"].as_f64().unwrap(),
+            std::f64::consts::FRAC_1_SQRT_2
+        ));
+    }
+
+    #[test]
+    fn single_thread_setting_is_correct() {
+        assert_eq!(thread_count(None), Ok(None));
+        assert_eq!(thread_count(Some("1")), Ok(Some(1)));
+        assert_eq!(thread_count(Some(" 4 ")), Ok(Some(4)));
+        assert!(thread_count(Some("0")).is_err());
+        assert!(thread_count(Some("many")).is_err());
+
+        // A parallel-sized batch gives identical replies on a one-thread pool
+        // and on the default pool.
+        let batch: Vec<Value> = (0..PARALLEL_BATCH_MIN * 2)
+            .map(|k| {
+                let data: Vec<f64> = (0..36).map(|i| ((i * 7 + k) % 11) as f64).collect();
+                serde_json::json!({ "data": data })
+            })
+            .collect();
+        let run = || {
+            let mut out = Vec::new();
+            let msg = invoke_message("1", "pod.eigs/eigenvalues", Value::Array(batch.clone()));
+            handle_invoke(&msg, &Session::default(), &mut out).unwrap();
+            out
+        };
+        let single = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(run);
+        assert_eq!(single, run());
+        let value: Value = serde_json::from_str(
+            &bencode_str(dict_get(&decode_all(&single)[0], "value").unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            value["results"].as_array().unwrap().len(),
+            PARALLEL_BATCH_MIN * 2
+        );
+    }
+
+    #[test]
+    fn spectrum_diff_small_perturbation() {
+        let a = serde_json::json!([[2.0, 1.0, 0.0], [1.0, 3.0, 1.0], [0.0, 1.0, 4.0]]);
+        let b = serde_json::json!([[2.001, 1.0, 0.0], [1.0, 3.0, 1.0], [0.0, 1.0, 4.0]]);
+        let out = invoke_var(
+            "pod.eigs/spectrum-diff",
+            &serde_json::json!({ "a": a, "b": b, "symmetric": true }),
+        )
+        .unwrap();
+        let max = out["max_abs_diff"].as_f64().unwrap();
+        assert!(max > 0.0 && max <= 1.0e-3 + 1.0e-12);
+        assert_eq!(out["diff"].as_array().unwrap().len(), 3);
+
+        let general = invoke_var(
+            "pod.eigs/spectrum-diff",
+            &serde_json::json!({ "a": a, "b": b }),
+        )
+        .unwrap();
+        assert!(general["max_abs_diff"].as_f64().unwrap() <= 1.0e-3 + 1.0e-12);
+
+        let small = serde_json::json!([[1.0]]);
+        assert!(invoke_var(
+            "pod.eigs/spectrum-diff",
+            &serde_json::json!({ "a": a, "b": small })
+        )
+        .is_err());
+    }
+
+    fn invoke_message(id: &str, var: &str, arg: Value) -> BTreeMap<Vec<u8>, Bencode> {
+        BTreeMap::from([
+            (b"op".to_vec(), Bencode::Bytes(b"invoke".to_vec())),
+            (b"id".to_vec(), Bencode::Bytes(id.as_bytes().to_vec())),
+            (b"var".to_vec(), Bencode::Bytes(var.as_bytes().to_vec())),
+            (
+                b"args".to_vec(),
+                Bencode::Bytes(serde_json::json!([arg]).to_string().into_bytes()),
+            ),
+        ])
+    }
+
+    #[test]
+    fn native_bencode_value_format() {
+        let mut data = vec![0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = 1.0;
+        }
+        data[4] = 1.0e-3;
+        data[5 * 6 + 4] = 1.0;
+        data[4 * 6 + 4] = 0.0;
+        let arg = serde_json::json!({ "data": data, "value_format": "bencode" });
+        let mut out = Vec::new();
+        handle_invoke(
+            &invoke_message("1", "pod.eigs/coherence", arg),
+            &Session::default(),
+            &mut out,
+        )
+        .unwrap();
+        let msgs = decode_all(&out);
+        let value = match dict_get(&msgs[0], "value") {
+            Some(Bencode::Dict(d)) => d.clone(),
+            other => panic!("expected native dict, got {:?}", other),
+        };
+        match dict_get(&value, "columns") {
+            Some(Bencode::List(items)) => {
+                assert!(matches!(items[..], [Bencode::Int(4), Bencode::Int(5)]))
+            }
+            other => panic!("expected int list, got {:?}", other),
+        }
+        let coherence = dict_get(&value, "coherence").and_then(bencode_str).unwrap();
+        assert!(coherence.parse::<f64>().unwrap() > 0.999);
+
+        let arg = serde_json::json!({ "data": data, "value_format": "xml" });
+        let mut out = Vec::new();
+        handle_invoke(
+            &invoke_message("2", "pod.eigs/coherence", arg),
+            &Session::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert!(dict_get(&decode_all(&out)[0], "ex-message").is_some());
+    }
+
+    #[test]
+    fn departure_from_normality() {
+        let mut data = vec![0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = (i + 1) as f64;
+            if i < 5 {
+                data[i * 6 + i + 1] = 0.5;
+                data[(i + 1) * 6 + i] = 0.5;
+            }
+        }
+        let out = invoke_var(
+            "pod.eigs/departure-from-normality",
+            &serde_json::json!({ "data": data }),
+        )
+        .unwrap();
+        assert!(out["departure"].as_f64().unwrap() < 1.0e-6);
+
+        let mut upper = vec![0.0f64; 36];
+        for i in 0..6 {
+            upper[i * 6 + i] = (i + 1) as f64;
+        }
+        upper[1] = 5.0;
+        let out = invoke_var(
+            "pod.eigs/departure-from-normality",
+            &serde_json::json!({ "data": upper }),
+        )
+        .unwrap();
+        assert!(close_enough(out["departure"].as_f64().unwrap(), 5.0));
+    }
+
+    #[test]
+    fn column_output_layout_transposes_nesting() {
+        let a = serde_json::json!([[1.0, 2.0], [3.0, 4.0]]);
+        let b = serde_json::json!([[5.0]]);
+        let rows = invoke_var("pod.eigs/dsum", &serde_json::json!({ "a": a, "b": b })).unwrap();
+        let cols = invoke_var(
+            "pod.eigs/dsum",
+            &serde_json::json!({ "a": a, "b": b, "output_layout": "column" }),
+        )
+        .unwrap();
+        assert_eq!(rows["dsum"][0], serde_json::json!([1.0, 2.0, 0.0]));
+        assert_eq!(cols["dsum"][0], serde_json::json!([1.0, 3.0, 0.0]));
+        let m = parse_rows(&rows["dsum"], "dsum").unwrap();
+        assert_eq!(parse_rows(&cols["dsum"], "dsum").unwrap(), m.transpose());
+
+        let bad = serde_json::json!({ "a": a, "b": b, "output_layout": "diagonal" });
+        assert!(invoke_var("pod.eigs/dsum", &bad).is_err());
+    }
+
+    #[test]
+    fn verify_reports_small_errors() {
+        let mut data = vec![0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = 4.0 + i as f64;
+            if i < 5 {
+                data[i * 6 + i + 1] = 1.0;
+                data[(i + 1) * 6 + i] = 1.0;
+            }
+        }
+        let out = invoke_var("pod.eigs/verify", &serde_json::json!({ "data": data })).unwrap();
+        for key in [
+            "max_residual",
+            "orthogonality_error",
+            "reconstruction_error",
+        ] {
+            assert!(out[key].as_f64().unwrap() < 1.0e-10, "{} too large", key);
+        }
+        data[1] = 3.0;
+        assert!(invoke_var("pod.eigs/verify", &serde_json::json!({ "data": data })).is_err());
+    }
+
+    #[test]
+    fn balancing_reduces_norm_and_keeps_spectrum() {
+        let mut m = DMatrix::<f64>::zeros(6, 6);
+        for i in 0..6 {
+            for j in 0..6 {
+                m[(i, j)] = 1.0 + ((i * 6 + j) % 7) as f64;
+            }
+        }
+        let scales = [1.0, 1.0e3, 1.0e-3, 1.0e5, 1.0, 1.0e-4];
+        let badly = DMatrix::from_fn(6, 6, |i, j| m[(i, j)] * scales[i] / scales[j]);
+        let data: Vec<f64> = badly.transpose().iter().cloned().collect();
+        let out = invoke_var("pod.eigs/balance", &serde_json::json!({ "data": data })).unwrap();
+        assert!(
+            out["norm_after"].as_f64().unwrap() < 1.0e-3 * out["norm_before"].as_f64().unwrap()
+        );
+
+        let balanced = parse_rows(&out["balanced"], "balanced").unwrap();
+        let scaling =
+            DVector::from_vec(serde_json::from_value::<Vec<f64>>(out["scaling"].clone()).unwrap());
+        let d = DMatrix::from_diagonal(&scaling);
+        let d_inv = DMatrix::from_diagonal(&scaling.map(|x| 1.0 / x));
+        assert!((&d_inv * &badly * &d - &balanced).norm() < 1.0e-9 * balanced.norm());
+
+        let plain = general_eigenvalues(m).unwrap();
+        let via_flag = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "data": data, "balance": true }),
+        )
+        .unwrap();
+        for (expected, got) in plain
+            .iter()
+            .zip(via_flag["eigenvalues"].as_array().unwrap())
+        {
+            assert!((expected.re - got[0].as_f64().unwrap()).abs() < 1.0e-8);
+            assert!((expected.im - got[1].as_f64().unwrap()).abs() < 1.0e-8);
+        }
+    }
+
+    #[test]
+    fn eig_sweep_at_zero_matches_a() {
+        let a = serde_json::json!([[2.0, 1.0], [1.0, 2.0]]);
+        let b = serde_json::json!([[1.0, 0.0], [0.0, -1.0]]);
+        let input = serde_json::json!({ "a": a, "b": b, "ts": [0.0, 0.5, 1.0], "symmetric": true });
+        let out = invoke_var("pod.eigs/eig-sweep", &input).unwrap();
+        let results = out["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["t"], 0.0);
+        assert!(close_enough(
+            results[0]["eigenvalues"][0].as_f64().unwrap(),
+            1.0
+        ));
+        assert!(close_enough(
+            results[0]["eigenvalues"][1].as_f64().unwrap(),
+            3.0
+        ));
+        // A + B = [[3, 1], [1, 1]] has eigenvalues 2 -/+ sqrt 2.
+        assert!(close_enough(
+            results[2]["eigenvalues"][0].as_f64().unwrap(),
+            2.0 - 2.0f64.sqrt()
+        ));
+
+        let too_many = serde_json::json!({ "a": a, "b": b, "ts": vec![0.0; MAX_SWEEP_POINTS + 1] });
+        assert!(invoke_var("pod.eigs/eig-sweep", &too_many).is_err());
+    }
+
+    #[test]
+    fn every_invoke_gets_a_result_then_done() {
+        let mut missing_args = invoke_message("3", "pod.eigs/eigenvalues", Value::Null);
+        missing_args.remove(b"args".as_slice());
+        let mut bad_json = invoke_message("4", "pod.eigs/eigenvalues", Value::Null);
+        bad_json.insert(b"args".to_vec(), Bencode::Bytes(b"[{".to_vec()));
+        let cases = vec![
+            invoke_message(
+                "1",
+                "pod.eigs/eigenvalues",
+                serde_json::json!({ "data": vec![1.0; 36], "symmetric": true }),
+            ),
+            invoke_message("2", "pod.eigs/nope", serde_json::json!({})),
+            missing_args,
+            bad_json,
+            invoke_message(
+                "5",
+                "pod.eigs/eigenvalues",
+                serde_json::json!({ "data": [1.0] }),
+            ),
+        ];
+        for msg in cases {
+            let id = dict_get(&msg, "id").cloned();
+            let mut out = Vec::new();
+            handle_message(Bencode::Dict(msg), &mut Session::default(), &mut out).unwrap();
+            let msgs = decode_all(&out);
+            let status = |m: &BTreeMap<Vec<u8>, Bencode>| match dict_get(m, "status") {
+                Some(Bencode::List(items)) => {
+                    items.iter().filter_map(bencode_str).collect::<Vec<_>>()
+                }
+                _ => Vec::new(),
+            };
+            if dict_get(&msgs[0], "ex-message").is_some() {
+                assert_eq!(msgs.len(), 1);
+                assert_eq!(status(&msgs[0]), vec!["done", "error"]);
+            } else {
+                assert_eq!(msgs.len(), 2);
+                assert!(dict_get(&msgs[0], "value").is_some());
+                assert!(status(&msgs[0]).is_empty());
+                assert_eq!(status(&msgs[1]), vec!["done"]);
+            }
+            for msg in &msgs {
+                assert_eq!(
+                    dict_get(msg, "id").and_then(bencode_str),
+                    id.as_ref().and_then(bencode_str)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn subspace_update_converges_to_low_rank_span() {
+        let u1 = DVector::from_vec(vec![1.0, 1.0, 0.0, 0.0, 1.0, 0.0]).normalize();
+        let u2 = DVector::from_vec(vec![0.0, 1.0, -1.0, 0.0, 0.0, 1.0]);
+        let u2 = (&u2 - &u1 * u1.dot(&u2)).normalize();
+        let mut seed: u64 = 42;
+        let mut rnd = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+        };
+        let mut basis = vec![
+            vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.0],
+        ];
+        for _ in 0..2000 {
+            let x = &u1 * (3.0 * rnd()) + &u2 * (2.0 * rnd());
+            let input = serde_json::json!({
+                "basis": basis,
+                "x": x.iter().cloned().collect::<Vec<f64>>(),
+                "rate": 0.05,
+            });
+            let out = invoke_var("pod.eigs/subspace-update", &input).unwrap();
+            basis = serde_json::from_value(out["basis"].clone()).unwrap();
+        }
+        let w = DMatrix::from_columns(
+            &basis
+                .iter()
+                .map(|b| DVector::from_vec(b.clone()))
+                .collect::<Vec<_>>(),
+        );
+        let u = DMatrix::from_columns(&[u1, u2]);
+        let outside = &w - &u * (u.transpose() * &w);
+        assert!(outside.norm() < 1.0e-3);
+        assert!((w.transpose() * &w - DMatrix::<f64>::identity(2, 2)).norm() < 1.0e-9);
+    }
+
+    #[test]
+    fn lstsq_overdetermined_line_fit() {
+        let a = serde_json::json!([[1.0, 0.0], [1.0, 1.0], [1.0, 2.0], [1.0, 3.0]]);
+        let b = [1.1, 2.9, 4.9, 7.1];
+        let out = invoke_var("pod.eigs/lstsq", &serde_json::json!({ "a": a, "b": b })).unwrap();
+        let am = parse_rows(&a, "a").unwrap();
+        let bv = DVector::from_row_slice(&b);
+        let normal = (am.transpose() * &am).try_inverse().unwrap() * am.transpose() * &bv;
+        let x = out["x"].as_array().unwrap();
+        assert!(close_enough(x[0].as_f64().unwrap(), normal[0]));
+        assert!(close_enough(x[1].as_f64().unwrap(), normal[1]));
+        assert!(close_enough(
+            out["residual_norm"].as_f64().unwrap(),
+            (&am * normal - bv).norm()
+        ));
+        assert_eq!(out["rank"], 2);
+
+        assert!(invoke_var(
+            "pod.eigs/lstsq",
+            &serde_json::json!({ "a": a, "b": [1.0, 2.0] })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn svd_energy_is_monotone_and_ends_at_one() {
+        let data: Vec<f64> = (0..36).map(|i| ((i * 7) % 11) as f64 - 5.0).collect();
+        let out = invoke_var("pod.eigs/svd-energy", &serde_json::json!({ "data": data })).unwrap();
+        let energy: Vec<f64> = serde_json::from_value(out["cumulative_energy"].clone()).unwrap();
+        let values: Vec<f64> = serde_json::from_value(out["singular_values"].clone()).unwrap();
+        assert_eq!(energy.len(), 6);
+        assert!(close_enough(energy[5], 1.0));
+        assert!(energy.windows(2).all(|w| w[0] <= w[1]));
+        assert!(values.windows(2).all(|w| w[0] >= w[1]));
+
+        assert!(invoke_var(
+            "pod.eigs/svd-energy",
+            &serde_json::json!({ "data": vec![0.0; 36] })
+        )
+        .is_err());
+
+        let rows = serde_json::json!([[3.0, 0.0, 0.0], [0.0, 4.0, 0.0]]);
+        let out = invoke_var("pod.eigs/svd-energy", &serde_json::json!({ "rows": rows })).unwrap();
+        assert_eq!(out["singular_values"], serde_json::json!([4.0, 3.0]));
+        assert_eq!(out["cumulative_energy"], serde_json::json!([0.64, 1.0]));
+    }
+
+    #[test]
+    fn low_rank_approx_extremes() {
+        let data: Vec<f64> = (0..36).map(|i| ((i * 5) % 13) as f64 - 6.0).collect();
+        let m = DMatrix::from_row_slice(6, 6, &data);
+
+        let full = invoke_var(
+            "pod.eigs/low-rank-approx",
+            &serde_json::json!({ "data": data, "k": 6 }),
+        )
+        .unwrap();
+        let approx = parse_rows(&full["approximation"], "approximation").unwrap();
+        assert!((approx - &m).norm() < 1.0e-9);
+        assert!(full["error"].as_f64().unwrap() < 1.0e-9);
+
+        let zero = invoke_var(
+            "pod.eigs/low-rank-approx",
+            &serde_json::json!({ "data": data, "k": 0 }),
+        )
+        .unwrap();
+        let approx = parse_rows(&zero["approximation"], "approximation").unwrap();
+        assert_eq!(approx.norm(), 0.0);
+        assert!(close_enough(zero["error"].as_f64().unwrap(), m.norm()));
+
+        assert!(invoke_var(
+            "pod.eigs/low-rank-approx",
+            &serde_json::json!({ "data": data, "k": 7 })
+        )
+        .is_err());
+
+        let rows = serde_json::json!([
+            [2.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0]
+        ]);
+        let out = invoke_var(
+            "pod.eigs/low-rank-approx",
+            &serde_json::json!({ "rows": rows, "k": 1 }),
+        )
+        .unwrap();
+        let approx = parse_rows(&out["approximation"], "approximation").unwrap();
+        assert_eq!((approx.nrows(), approx.ncols()), (3, 4));
+        assert!((approx[(0, 0)] - 2.0).abs() < 1.0e-12);
+        assert!(approx[(1, 2)].abs() < 1.0e-12);
+        assert!(close_enough(out["error"].as_f64().unwrap(), 1.0));
+        assert!(invoke_var(
+            "pod.eigs/low-rank-approx",
+            &serde_json::json!({ "rows": rows, "k": 4 })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn sparsity_of_tridiagonal() {
+        let mut data = vec![0.0f64; 36];
+        for i in 0..6 {
+            data[i * 6 + i] = 2.0;
+            if i < 5 {
+                data[i * 6 + i + 1] = -1.0;
+                data[(i + 1) * 6 + i] = -1.0;
+            }
+        }
+        let out = invoke_var("pod.eigs/sparsity", &serde_json::json!({ "data": data })).unwrap();
+        assert_eq!(out["bandwidth"], 1);
+        assert_eq!(out["is_banded"], true);
+        assert_eq!(out["zeros"], 36 - 16);
+
+        let dense = invoke_var(
+            "pod.eigs/sparsity",
+            &serde_json::json!({ "data": vec![1.0; 36] }),
+        )
+        .unwrap();
+        assert_eq!(dense["bandwidth"], 5);
+        assert_eq!(dense["is_banded"], false);
+    }
+
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+    }
+
+    #[test]
+    fn broken_pipe_exits_cleanly() {
+        let mut input: &[u8] = b"d2:id1:12:op8:describeed2:id1:22:op8:describee";
+        assert!(serve(&mut input, &mut ClosedPipe).is_ok());
+    }
+
+    #[test]
+    fn apply_identity_and_known_matrix() {
+        let mut eye = vec![0.0f64; 36];
+        for i in 0..6 {
+            eye[i * 6 + i] = 1.0;
+        }
+        let vectors = serde_json::json!([
+            [1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+            [0.0, -1.0, 0.0, 0.5, 0.0, 0.0]
+        ]);
+        let out = invoke_var(
+            "pod.eigs/apply",
+            &serde_json::json!({ "data": eye, "vectors": vectors }),
+        )
+        .unwrap();
+        assert_eq!(out["results"], vectors);
+
+        let data: Vec<f64> = (0..36).map(|i| (i % 6) as f64).collect();
+        let out = invoke_var(
+            "pod.eigs/apply",
+            &serde_json::json!({ "data": data, "vectors": [vec![1.0; 6]] }),
+        )
+        .unwrap();
+        assert_eq!(out["results"][0], serde_json::json!(vec![15.0; 6]));
+
+        assert!(invoke_var(
+            "pod.eigs/apply",
+            &serde_json::json!({ "data": data, "vectors": [vec![1.0; 5]] })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn invariant_subspace_dimensions() {
+        // Spectrum {1, 1, 1, 4, 4, 9} rotated out of the standard basis.
+        let diag = DMatrix::from_diagonal(&DVector::from_vec(vec![1.0, 1.0, 1.0, 4.0, 4.0, 9.0]));
+        let q = DMatrix::from_fn(6, 6, |i, j| ((i * 6 + j) as f64).sin())
+            .qr()
+            .q();
+        let a = &q * diag * q.transpose();
+        let a = (&a + a.transpose()) * 0.5;
+        let data: Vec<f64> = a.transpose().iter().cloned().collect();
+        let out = invoke_var(
+            "pod.eigs/common-invariant-subspaces",
+            &serde_json::json!({ "data": data }),
+        )
+        .unwrap();
+        assert_eq!(out["dimensions"], serde_json::json!([3, 2, 1]));
+        assert_eq!(out["commutant_dimension"], 14);
+        assert!(close_enough(
+            out["subspaces"][1]["eigenvalue"].as_f64().unwrap(),
+            4.0
+        ));
+    }
+
+    #[test]
+    fn frobenius_inner_matches_trace() {
+        let a = serde_json::json!([[1.0, 2.0, 0.5], [-3.0, 4.0, 1.0]]);
+        let b = serde_json::json!([[0.5, -1.0, 2.0], [2.0, 1.5, -4.0]]);
+        let out = invoke_var(
+            "pod.eigs/frobenius-inner",
+            &serde_json::json!({ "a": a, "b": b }),
+        )
+        .unwrap();
+        let (ma, mb) = (parse_rows(&a, "a").unwrap(), parse_rows(&b, "b").unwrap());
+        let expected = (ma.transpose() * &mb).trace();
+        assert!(close_enough(out["inner"].as_f64().unwrap(), expected));
+        let out = invoke_var(
+            "pod.eigs/frobenius-inner",
+            &serde_json::json!({ "a": a, "b": a }),
+        )
+        .unwrap();
+        assert!(close_enough(
+            out["inner"].as_f64().unwrap(),
+            ma.norm_squared()
+        ));
+    }
+
+    #[test]
+    fn bare_var_name_resolves() {
+        let input = serde_json::json!({ "a": [[1.0]], "b": [[2.0]] });
+        let bare = invoke_var("frobenius-inner", &input).unwrap();
+        assert_eq!(
+            bare,
+            invoke_var("pod.eigs/frobenius-inner", &input).unwrap()
+        );
+        assert!(invoke_var("other.ns/frobenius-inner", &input).is_err());
+        assert!(invoke_var("no-such-var", &input).is_err());
+    }
+
+    #[test]
+    fn spectral_filter_identity_and_low_pass() {
+        let rows = serde_json::json!([
+            [2.0, 1.0, 0.0, 0.0, 0.0, 0.0],
+            [1.0, 2.0, 0.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 5.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 6.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 7.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 0.0, 8.0]
+        ]);
+        let a = parse_rows(&rows, "rows").unwrap();
+        let all = serde_json::json!({ "rows": rows, "filter": { "range": [-100.0, 100.0] } });
+        let out = invoke_var("pod.eigs/spectral-filter", &all).unwrap();
+        let filtered = parse_rows(&out["filtered"], "filtered").unwrap();
+        assert!((filtered - &a).norm() < 1e-9);
+
+        // Spectrum {1, 3, 5, 6, 7, 8}: dropping λ >= 2 leaves only the λ = 1 component.
+        let low = serde_json::json!({ "rows": rows, "filter": { "gains": [[2.0, 0.0]] } });
+        let out = invoke_var("pod.eigs/spectral-filter", &low).unwrap();
+        assert_eq!(
+            out["gains"],
+            serde_json::json!([1.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+        );
+        let filtered = parse_rows(&out["filtered"], "filtered").unwrap();
+        let mut expected = DMatrix::zeros(6, 6);
+        expected[(0, 0)] = 0.5;
+        expected[(1, 1)] = 0.5;
+        expected[(0, 1)] = -0.5;
+        expected[(1, 0)] = -0.5;
+        assert!((filtered - expected).norm() < 1e-9);
+
+        let bad =
+            serde_json::json!({ "rows": rows, "filter": { "gains": [[2.0, 0.0], [1.0, 1.0]] } });
+        assert!(invoke_var("pod.eigs/spectral-filter", &bad).is_err());
+    }
+
+    #[test]
+    fn pfaffian_squares_to_determinant() {
+        let upper = DMatrix::from_fn(6, 6, |i, j| {
+            if i < j {
+                ((i * 7 + j * 3) as f64).cos()
+            } else {
+                0.0
+            }
+        });
+        let a = &upper - upper.transpose();
+        let data: Vec<f64> = a.transpose().iter().cloned().collect();
+        let out = invoke_var("pod.eigs/pfaffian", &serde_json::json!({ "data": data })).unwrap();
+        let pf = out["pfaffian"].as_f64().unwrap();
+        assert!(close_enough(pf * pf, a.determinant()));
+        assert!(pf.abs() > 1e-6);
+
+        // Block-diagonal [[0 a] [-a 0]] blocks: pf is the product of the a's.
+        let mut blocks = DMatrix::zeros(6, 6);
+        for (k, v) in [2.0, -3.0, 0.5].iter().enumerate() {
+            blocks[(2 * k, 2 * k + 1)] = *v;
+            blocks[(2 * k + 1, 2 * k)] = -*v;
+        }
+        assert!(close_enough(pfaffian(&blocks), -3.0));
+
+        let mut not_skew = a.clone();
+        not_skew[(0, 1)] += 1.0;
+        let data: Vec<f64> = not_skew.transpose().iter().cloned().collect();
+        assert!(invoke_var("pod.eigs/pfaffian", &serde_json::json!({ "data": data })).is_err());
+    }
+
+    #[test]
+    fn principal_angles_identical_and_orthogonal() {
+        let a = serde_json::json!([[1.0, 1.0, 0.0, 0.0], [0.0, 1.0, 1.0, 0.0]]);
+        let same = serde_json::json!([[1.0, 2.0, 1.0, 0.0], [1.0, 0.0, -1.0, 0.0]]);
+        let out = invoke_var(
+            "pod.eigs/principal-angles",
+            &serde_json::json!({ "a": a, "b": same }),
+        )
+        .unwrap();
+        for angle in out["angles"].as_array().unwrap() {
+            assert!(angle.as_f64().unwrap().abs() < 1e-6);
+        }
+        let ortho = serde_json::json!([[0.0, 0.0, 0.0, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/principal-angles",
+            &serde_json::json!({ "a": a, "b": ortho }),
+        )
+        .unwrap();
+        assert!(close_enough(
+            out["angles"][0].as_f64().unwrap(),
+            std::f64::consts::FRAC_PI_2
+        ));
+
+        let dependent = serde_json::json!([[1.0, 0.0, 0.0, 0.0], [2.0, 0.0, 0.0, 0.0]]);
+        assert!(invoke_var(
+            "pod.eigs/principal-angles",
+            &serde_json::json!({ "a": dependent, "b": a })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn logdet_spd_matches_log_eigenvalues() {
+        let b = DMatrix::from_fn(6, 6, |i, j| ((i * 5 + j * 2) as f64).sin());
+        let a = &b * b.transpose() + DMatrix::identity(6, 6);
+        let data: Vec<f64> = a.transpose().iter().cloned().collect();
+        let out = invoke_var("pod.eigs/logdet-spd", &serde_json::json!({ "data": data })).unwrap();
+        let expected: f64 = symmetric_eigenvalues(a).iter().map(|l| l.ln()).sum();
+        assert!(close_enough(out["logdet"].as_f64().unwrap(), expected));
+
+        let mut indefinite = vec![0.0; 36];
+        indefinite[0] = -1.0;
+        assert!(invoke_var(
+            "pod.eigs/logdet-spd",
+            &serde_json::json!({ "data": indefinite })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn trace_inv_product_matches_explicit_inverse() {
+        let a = serde_json::json!([[4.0, 1.0, 0.0], [2.0, 3.0, 1.0], [0.0, 1.0, 5.0]]);
+        let b = serde_json::json!([[1.0, 2.0, 3.0], [0.0, -1.0, 4.0], [2.0, 0.5, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/trace-inv-product",
+            &serde_json::json!({ "a": a, "b": b }),
+        )
+        .unwrap();
+        let (ma, mb) = (parse_rows(&a, "a").unwrap(), parse_rows(&b, "b").unwrap());
+        let expected = (ma.try_inverse().unwrap() * mb).trace();
+        assert!(close_enough(out["trace"].as_f64().unwrap(), expected));
+
+        let singular = serde_json::json!([[1.0, 2.0], [2.0, 4.0]]);
+        let b = serde_json::json!([[1.0, 0.0], [0.0, 1.0]]);
+        let err = invoke_var(
+            "pod.eigs/trace-inv-product",
+            &serde_json::json!({ "a": singular, "b": b }),
+        );
+        assert_eq!(err.unwrap_err(), "a is singular");
+    }
+
+    #[test]
+    fn schur_blocks_label_real_and_complex_pair() {
+        // One rotation-scaling block (eigenvalues 1 ± 2i) plus four reals,
+        // mixed by an orthogonal change of basis.
+        let mut d = DMatrix::from_diagonal(&DVector::from_vec(vec![1.0, 1.0, -1.0, 3.0, 5.0, 7.0]));
+        d[(0, 1)] = -2.0;
+        d[(1, 0)] = 2.0;
+        let q = DMatrix::from_fn(6, 6, |i, j| ((i * 3 + j * 5) as f64).cos())
+            .qr()
+            .q();
+        let a = &q * d * q.transpose();
+        let data: Vec<f64> = a.transpose().iter().cloned().collect();
+        let input = serde_json::json!({ "data": data, "with_schur": true, "schur_blocks": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        let blocks = out["schur_blocks"].as_array().unwrap();
+        let pairs: Vec<&Value> = blocks
+            .iter()
+            .filter(|b| b["kind"] == "complex_pair")
+            .collect();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0]["size"], 2);
+        assert!(close_enough(
+            pairs[0]["eigenvalues"][0][0].as_f64().unwrap(),
+            1.0
+        ));
+        assert!(close_enough(
+            pairs[0]["eigenvalues"][0][1].as_f64().unwrap().abs(),
+            2.0
+        ));
+        assert_eq!(blocks.iter().filter(|b| b["kind"] == "real").count(), 4);
+        assert_eq!(out["schur"].as_array().unwrap().len(), 6);
+
+        let no_schur = serde_json::json!({ "data": data, "schur_blocks": true });
+        assert!(invoke_var("pod.eigs/eigenvalues", &no_schur).is_err());
+    }
+
+    #[test]
+    fn involution_detects_reflection() {
+        let v = DVector::from_vec(vec![1.0, -2.0, 0.5, 3.0, 1.0, -1.0]);
+        let reflection: DMatrix<f64> =
+            DMatrix::identity(6, 6) - &v * v.transpose() * (2.0 / v.norm_squared());
+        let data: Vec<f64> = reflection.transpose().iter().cloned().collect();
+        let out = invoke_var("pod.eigs/involution?", &serde_json::json!({ "data": data })).unwrap();
+        assert_eq!(out["involution"], true);
+        assert!(out["residual"].as_f64().unwrap() < 1e-9);
+
+        let general: Vec<f64> = (0..36).map(|i| i as f64).collect();
+        let out = invoke_var(
+            "pod.eigs/involution?",
+            &serde_json::json!({ "data": general }),
+        )
+        .unwrap();
+        assert_eq!(out["involution"], false);
+    }
+
+    #[test]
+    fn eigenvalue_multiplicity_of_jordan_block() {
+        // A 3x3 Jordan block for λ = 2 plus a separate simple λ = 2.
+        let mut j = DMatrix::from_diagonal(&DVector::from_vec(vec![2.0, 2.0, 2.0, 2.0, -1.0, 5.0]));
+        j[(0, 1)] = 1.0;
+        j[(1, 2)] = 1.0;
+        let s = DMatrix::from_fn(6, 6, |r, c| {
+            if r == c {
+                2.0
+            } else {
+                0.1 * ((r + 2 * c) as f64).sin()
+            }
+        });
+        let a = &s * j * s.clone().try_inverse().unwrap();
+        let data: Vec<f64> = a.transpose().iter().cloned().collect();
+        let out = invoke_var(
+            "pod.eigs/eigenvalue-multiplicity",
+            &serde_json::json!({ "data": data, "eigenvalue": 2.0, "tol": 1.0e-4 }),
+        )
+        .unwrap();
+        assert_eq!(out["algebraic"], 4);
+        assert_eq!(out["geometric"], 2);
+        assert_eq!(out["defective"], true);
+
+        let out = invoke_var(
+            "pod.eigs/eigenvalue-multiplicity",
+            &serde_json::json!({ "data": data, "eigenvalue": 5.0 }),
+        )
+        .unwrap();
+        assert_eq!(
+            (out["algebraic"].clone(), out["geometric"].clone()),
+            (1.into(), 1.into())
+        );
+    }
+
+    #[test]
+    fn string_entries_parse_as_fractions() {
+        let mut rows = vec![vec![serde_json::json!(0.0); 6]; 6];
+        for (i, row) in rows.iter_mut().enumerate() {
+            row[i] = serde_json::json!("1/2");
+        }
+        rows[0][0] = serde_json::json!(" 0.25 ");
+        let input =
+            serde_json::json!({ "rows": rows, "symmetric": true, "allow_string_entries": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        assert_eq!(
+            out["eigenvalues"],
+            serde_json::json!([0.25, 0.5, 0.5, 0.5, 0.5, 0.5])
+        );
+
+        let strict = serde_json::json!({ "rows": rows, "symmetric": true });
+        assert!(invoke_var("pod.eigs/eigenvalues", &strict).is_err());
+        // Decimal strings are gated by the flag too, not only fractions.
+        let decimal = serde_json::json!({ "rows": [[" 0.25 ", 0.0], [0.0, "1e-1"]] });
+        assert!(invoke_var("pod.eigs/eigenvalues", &decimal).is_err());
+
+        rows[2][3] = serde_json::json!("1/0");
+        let bad = serde_json::json!({ "rows": rows, "allow_string_entries": true });
+        let err = invoke_var("pod.eigs/eigenvalues", &bad).unwrap_err();
+        assert!(err.contains("(2, 3)"), "{}", err);
+    }
+
+    #[test]
+    fn procrustes_recovers_rotation() {
+        let (c, s) = (0.6f64, 0.8f64);
+        let r0 = DMatrix::from_row_slice(3, 3, &[c, -s, 0.0, s, c, 0.0, 0.0, 0.0, 1.0]);
+        let a = DMatrix::from_fn(3, 5, |i, j| ((i * 5 + j) as f64).sin() + i as f64);
+        let b = &r0 * &a;
+        let rows = |m: &DMatrix<f64>| matrix_value(m, Layout::Row);
+        let out = invoke_var(
+            "pod.eigs/procrustes",
+            &serde_json::json!({ "a": rows(&a), "b": rows(&b) }),
+        )
+        .unwrap();
+        let r = parse_rows(&out["rotation"], "rotation").unwrap();
+        assert!((r - r0).norm() < 1e-9);
+        assert!(out["residual"].as_f64().unwrap() < 1e-9);
+    }
+
+    #[test]
+    fn numerical_range_contains_eigenvalues() {
+        let mut a = DMatrix::from_fn(6, 6, |i, j| {
+            if j > i {
+                ((i + 2 * j) as f64).cos()
+            } else {
+                0.0
+            }
+        });
+        a.set_diagonal(&DVector::from_vec(vec![1.0, -2.0, 3.0, 0.5, 4.0, -1.0]));
+        let data: Vec<f64> = a.transpose().iter().cloned().collect();
+        let out = invoke_var(
+            "pod.eigs/in-numerical-range?",
+            &serde_json::json!({ "data": data, "z": [3.0, 0.0] }),
+        )
+        .unwrap();
+        assert_eq!(out["contained"], true);
+        let out = invoke_var(
+            "pod.eigs/in-numerical-range?",
+            &serde_json::json!({ "data": data, "z": 100.0 }),
+        )
+        .unwrap();
+        assert_eq!(out["contained"], false);
+        assert!(out["margin"].as_f64().unwrap() < 0.0);
+    }
+
+    #[test]
+    fn arbitrary_square_dimensions() {
+        let rows = serde_json::json!([[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 7.0]]);
+        let out = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "symmetric": true }),
+        )
+        .unwrap();
+        let values: Vec<f64> = serde_json::from_value(out["eigenvalues"].clone()).unwrap();
+        assert_eq!(values.len(), 3);
+        assert!(
+            close_enough(values[0], 1.0)
+                && close_enough(values[1], 3.0)
+                && close_enough(values[2], 7.0)
+        );
+
+        let data: Vec<f64> = (0..100)
+            .map(|i| if i % 11 == 0 { (i / 11) as f64 } else { 0.0 })
+            .collect();
+        let out = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "data": data, "symmetric": true }),
+        )
+        .unwrap();
+        assert_eq!(out["eigenvalues"].as_array().unwrap().len(), 10);
+
+        let wide = serde_json::json!({ "rows": vec![vec![1.0; 5]; 4] });
+        assert_eq!(
+            invoke_var("pod.eigs/eigenvalues", &wide).unwrap_err(),
+            "matrix must be square, got 4x5"
+        );
+        let ragged = serde_json::json!({ "data": vec![1.0; 7] });
+        assert!(invoke_var("pod.eigs/eigenvalues", &ragged).is_err());
+    }
+
+    #[test]
+    fn weighted_eigs_with_unit_and_uniform_weights() {
+        let rows = serde_json::json!([[4.0, 1.0, 0.0], [1.0, 3.0, -1.0], [0.0, -1.0, 2.0]]);
+        let plain = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "symmetric": true }),
+        )
+        .unwrap();
+        let ones = invoke_var(
+            "pod.eigs/weighted-eigs",
+            &serde_json::json!({ "rows": rows, "weights": [1, 1, 1] }),
+        )
+        .unwrap();
+        let twos = invoke_var(
+            "pod.eigs/weighted-eigs",
+            &serde_json::json!({ "rows": rows, "weights": [2, 2, 2] }),
+        )
+        .unwrap();
+        for i in 0..3 {
+            let l = plain["eigenvalues"][i].as_f64().unwrap();
+            assert!(close_enough(ones["eigenvalues"][i].as_f64().unwrap(), l));
+            assert!(close_enough(
+                twos["eigenvalues"][i].as_f64().unwrap(),
+                l / 2.0
+            ));
+        }
+        let bad = serde_json::json!({ "rows": rows, "weights": [1, 0, 1] });
+        assert!(invoke_var("pod.eigs/weighted-eigs", &bad).is_err());
+    }
+
+    #[test]
+    fn eigenvectors_of_diagonal_are_basis_vectors() {
+        let rows = serde_json::json!([[3.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 2.0]]);
+        let out = invoke_var(
+            "pod.eigs/eigenvectors",
+            &serde_json::json!({ "rows": rows, "symmetric": true }),
+        )
+        .unwrap();
+        assert_eq!(out["eigenvector_layout"], "row");
+        assert_eq!(out["eigenvalues"], serde_json::json!([1.0, 2.0, 3.0]));
+        assert_eq!(
+            out["eigenvectors"],
+            serde_json::json!([[0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]])
+        );
+
+        let out = invoke_var(
+            "pod.eigs/eigenvectors",
+            &serde_json::json!({ "rows": rows }),
+        )
+        .unwrap();
+        let expected = [1usize, 2, 0];
+        for (i, vector) in out["eigenvectors"].as_array().unwrap().iter().enumerate() {
+            for (j, entry) in vector.as_array().unwrap().iter().enumerate() {
+                let want = if j == expected[i] { 1.0 } else { 0.0 };
+                assert!(close_enough(entry[0].as_f64().unwrap(), want));
+                assert!(close_enough(entry[1].as_f64().unwrap(), 0.0));
+            }
+        }
+
+        let cols =
+            serde_json::json!({ "rows": rows, "symmetric": true, "output_layout": "column" });
+        let out = invoke_var("pod.eigs/eigenvectors", &cols).unwrap();
+        assert_eq!(out["eigenvector_layout"], "column");
+        assert_eq!(out["eigenvectors"][0], serde_json::json!([0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn general_eigenvectors_satisfy_eigen_equation() {
+        let a = DMatrix::from_row_slice(3, 3, &[0.0, -2.0, 1.0, 1.0, 0.0, 0.5, 0.0, 0.3, 2.0]);
+        let values = general_eigenvalues(a.clone()).unwrap();
+        let vectors = general_eigenvectors(&a, &values);
+        let ac = a.map(|x| Complex::new(x, 0.0));
+        for (l, v) in values.iter().zip(&vectors) {
+            assert!((&ac * v - v * *l).norm() < 1e-9);
+        }
     }
 
     #[test]
-    fn symmetric_block() {
-        let mut data = [0.0f64; 36];
-        data[0] = 2.0;
-        data[1] = 1.0;
-        data[6] = 1.0;
-        data[7] = 2.0;
-        let matrix = SMatrix::from_row_slice(&data);
-        let out = eigenvalues_for(matrix, true).unwrap();
-        let vals = out.get("eigenvalues").unwrap().as_array().unwrap();
-        let mut nums: Vec<f64> = vals.iter().map(|v| v.as_f64().unwrap()).collect();
-        nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
-        assert_eq!(nums.len(), 6);
-        assert!(close_enough(nums[5], 3.0));
-        assert!(close_enough(nums[4], 1.0));
+    fn near_defective_matrix_warns() {
+        let rows = serde_json::json!([[1.0, 1.0e4, 0.0], [0.0, 1.00001, 0.0], [0.0, 0.0, 2.0]]);
+        let input = serde_json::json!({ "rows": rows, "check_conditioning": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        assert!(out["eigenvector_condition"].as_f64().unwrap() > 1.0e8);
+        assert!(out["warning"]
+            .as_str()
+            .unwrap()
+            .contains("close to defective"));
+
+        let normal = serde_json::json!({ "rows": [[1.0, 2.0, 0.0], [-2.0, 1.0, 0.0], [0.0, 0.0, 3.0]], "check_conditioning": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &normal).unwrap();
+        assert!(out.get("warning").is_none());
+        assert!(close_enough(
+            out["eigenvector_condition"].as_f64().unwrap(),
+            1.0
+        ));
+    }
+
+    #[test]
+    fn schur_epsilon_and_max_iter_options() {
+        let rows = serde_json::json!([[0.0, -2.0, 1.0], [1.0, 0.0, 0.5], [0.0, 0.3, 2.0]]);
+        let base =
+            invoke_var("pod.eigs/eigenvalues", &serde_json::json!({ "rows": rows })).unwrap();
+        let tuned = serde_json::json!({ "rows": rows, "epsilon": 1.0e-10, "max_iter": 1000, "with_meta": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &tuned).unwrap();
+        assert_eq!(out["meta"]["epsilon"].as_f64().unwrap(), 1.0e-10);
+        for (a, b) in out["eigenvalues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(base["eigenvalues"].as_array().unwrap())
+        {
+            assert!(close_enough(a[0].as_f64().unwrap(), b[0].as_f64().unwrap()));
+            assert!(close_enough(a[1].as_f64().unwrap(), b[1].as_f64().unwrap()));
+        }
+        for bad in [
+            serde_json::json!({ "rows": rows, "max_iter": 0 }),
+            serde_json::json!({ "rows": rows, "max_iter": 2.5 }),
+            serde_json::json!({ "rows": rows, "epsilon": 0.0 }),
+            serde_json::json!({ "rows": rows, "epsilon": "tiny" }),
+        ] {
+            assert!(invoke_var("pod.eigs/eigenvalues", &bad).is_err());
+        }
+        let starved = serde_json::json!({ "rows": rows, "max_iter": 1 });
+        let err = invoke_var("pod.eigs/eigenvalues", &starved).unwrap_err();
+        assert!(err.contains("within 1 iterations"), "{}", err);
+    }
+
+    #[test]
+    fn determinant_of_identity_and_singular() {
+        let identity: Vec<f64> = (0..16)
+            .map(|i| if i % 5 == 0 { 1.0 } else { 0.0 })
+            .collect();
+        let out = invoke_var(
+            "pod.eigs/determinant",
+            &serde_json::json!({ "data": identity }),
+        )
+        .unwrap();
+        assert!(close_enough(out["determinant"].as_f64().unwrap(), 1.0));
+
+        let singular = serde_json::json!([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        let out = invoke_var(
+            "pod.eigs/determinant",
+            &serde_json::json!({ "rows": singular }),
+        )
+        .unwrap();
+        assert!(out["determinant"].as_f64().unwrap().abs() < 1e-9);
+
+        let rotation = serde_json::json!([[0.0, -2.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, 3.0]]);
+        let out = invoke_var(
+            "pod.eigs/determinant",
+            &serde_json::json!({ "rows": rotation }),
+        )
+        .unwrap();
+        assert!(close_enough(out["determinant"].as_f64().unwrap(), 12.0));
+    }
+
+    #[test]
+    fn deviatoric_part_is_trace_free() {
+        let rows = serde_json::json!([[3.0, 1.0, 2.0], [0.0, 5.0, -1.0], [4.0, 2.0, 7.0]]);
+        let input = serde_json::json!({ "rows": rows, "with_isotropic": true });
+        let out = invoke_var("pod.eigs/deviatoric", &input).unwrap();
+        let dev = parse_rows(&out["deviatoric"], "deviatoric").unwrap();
+        let iso = parse_rows(&out["isotropic"], "isotropic").unwrap();
+        assert!(dev.trace().abs() < 1e-12);
+        assert!(close_enough(iso[(0, 0)], 5.0));
+        assert!((dev + iso - parse_rows(&rows, "rows").unwrap()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn spectral_spread_of_evenly_spaced_diagonal() {
+        let rows = serde_json::json!([
+            [7.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 5.0, 0.0],
+            [0.0, 0.0, 0.0, 3.0]
+        ]);
+        for symmetric in [true, false] {
+            let out = invoke_var(
+                "pod.eigs/spectral-spread",
+                &serde_json::json!({ "rows": rows, "symmetric": symmetric }),
+            )
+            .unwrap();
+            assert!(close_enough(out["spread"].as_f64().unwrap(), 6.0));
+            assert!(close_enough(out["mean_gap"].as_f64().unwrap(), 2.0));
+            assert!(close_enough(
+                out["std_dev"].as_f64().unwrap(),
+                5.0f64.sqrt()
+            ));
+        }
+    }
+
+    #[test]
+    fn column_layout_data_is_transposed() {
+        let data: Vec<f64> = (1..=9).map(|i| i as f64).collect();
+        let row = read_matrix(&serde_json::json!({ "data": data })).unwrap();
+        let col = read_matrix(&serde_json::json!({ "data": data, "layout": "column" })).unwrap();
+        assert_eq!(row[(0, 1)], 2.0);
+        assert_eq!(col[(1, 0)], 2.0);
+        assert_eq!(col, row.transpose());
+        assert_eq!(
+            read_matrix(&serde_json::json!({ "data": data, "layout": "row" })).unwrap(),
+            row
+        );
+        assert!(read_matrix(&serde_json::json!({ "data": data, "layout": "diagonal" })).is_err());
+    }
+
+    #[test]
+    fn nearest_orthogonal_repairs_drifted_rotation() {
+        let (c, s) = (0.6f64, 0.8f64);
+        let rotation = DMatrix::from_row_slice(3, 3, &[c, -s, 0.0, s, c, 0.0, 0.0, 0.0, 1.0]);
+        let drift = DMatrix::from_fn(3, 3, |i, j| 1.0e-4 * ((i * 3 + j) as f64).sin());
+        let drifted = &rotation + drift;
+        let out = invoke_var(
+            "pod.eigs/nearest-orthogonal",
+            &serde_json::json!({ "rows": matrix_value(&drifted, Layout::Row) }),
+        )
+        .unwrap();
+        let q = parse_rows(&out["orthogonal"], "orthogonal").unwrap();
+        assert!((q.transpose() * &q - DMatrix::identity(3, 3)).norm() < 1e-12);
+        assert!((&q - &rotation).norm() < 1e-3);
+        assert!(close_enough(
+            out["distance"].as_f64().unwrap(),
+            (&q - &drifted).norm()
+        ));
+    }
+
+    #[test]
+    fn multiplicities_cluster_double_eigenvalues() {
+        let s = DMatrix::from_fn(5, 5, |r, c| {
+            if r == c {
+                2.0
+            } else {
+                0.2 * ((r + 3 * c) as f64).cos()
+            }
+        });
+        let s_inv = s.clone().try_inverse().unwrap();
+        let mut d = DMatrix::from_diagonal(&DVector::from_vec(vec![4.0, 4.0, -1.0, 7.0, 7.0]));
+        let out = eigenvalues_for(
+            &s * &d * &s_inv,
+            &EigOptions {
+                multiplicities: true,
+                ..EigOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(out["multiplicities"], serde_json::json!([1, 2, 2]));
+        assert!(close_enough(
+            out["eigenvalues"][1][0].as_f64().unwrap(),
+            4.0
+        ));
+
+        // Two copies of the pair 1 ± 2i: the conjugates stay apart.
+        for (i, j) in [(0, 1), (3, 4)] {
+            d[(i, i)] = 1.0;
+            d[(j, j)] = 1.0;
+            d[(i, j)] = -2.0;
+            d[(j, i)] = 2.0;
+        }
+        let out = eigenvalues_for(
+            &s * &d * &s_inv,
+            &EigOptions {
+                multiplicities: true,
+                ..EigOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(out["multiplicities"], serde_json::json!([1, 2, 2]));
+        assert!(close_enough(
+            out["eigenvalues"][1][1].as_f64().unwrap(),
+            -2.0
+        ));
+        assert!(close_enough(
+            out["eigenvalues"][2][1].as_f64().unwrap(),
+            2.0
+        ));
+
+        let sym = serde_json::json!({ "rows": [[3.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 1.0]], "symmetric": true, "multiplicities": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &sym).unwrap();
+        assert_eq!(out["eigenvalues"], serde_json::json!([1.0, 3.0]));
+        assert_eq!(out["multiplicities"], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn socket_port_selection() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(socket_port(&args(&[]), None), Ok(None));
+        assert_eq!(
+            socket_port(&args(&["--socket", "7777"]), None),
+            Ok(Some(7777))
+        );
+        assert_eq!(socket_port(&args(&[]), Some("socket")), Ok(Some(0)));
+        assert!(socket_port(&args(&["--socket"]), None).is_err());
+        assert!(socket_port(&args(&["--socket", "http"]), None).is_err());
+    }
+
+    #[test]
+    fn socket_transport_survives_disconnects() {
+        use std::net::{TcpListener, TcpStream};
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || accept_loop(&listener));
+
+        let exchange = |stream: &mut TcpStream, msg: &Bencode| {
+            stream.write_all(&encode_bencode(msg)).unwrap();
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut chunk).unwrap();
+                buf.extend_from_slice(&chunk[..n]);
+                if let Ok((Bencode::Dict(d), _)) = parse_at(&buf, 0) {
+                    return d;
+                }
+            }
+        };
+        let describe = Bencode::Dict(BTreeMap::from([(
+            b"op".to_vec(),
+            Bencode::Bytes(b"describe".to_vec()),
+        )]));
+        let mut first = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        assert!(dict_get(&exchange(&mut first, &describe), "namespaces").is_some());
+        drop(first);
+
+        let mut second = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let invoke = Bencode::Dict(invoke_message(
+            "1",
+            "pod.eigs/frobenius-inner",
+            serde_json::json!({ "a": [[2.0]], "b": [[3.0]] }),
+        ));
+        let resp = exchange(&mut second, &invoke);
+        assert_eq!(
+            bencode_str(dict_get(&resp, "value").unwrap()).unwrap(),
+            r#"{"inner":6.0}"#
+        );
+        let shutdown = Bencode::Dict(BTreeMap::from([(
+            b"op".to_vec(),
+            Bencode::Bytes(b"shutdown".to_vec()),
+        )]));
+        second.write_all(&encode_bencode(&shutdown)).unwrap();
+        assert!(server.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn socket_port_file_is_removed_on_shutdown() {
+        use std::net::TcpStream;
+        let server = std::thread::spawn(|| serve_socket(0));
+        let port_file = port_file_name();
+        let started = Instant::now();
+        let port: u16 = loop {
+            if let Some(port) = std::fs::read_to_string(&port_file)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+            {
+                break port;
+            }
+            assert!(started.elapsed().as_secs() < 10, "port file never appeared");
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let shutdown = Bencode::Dict(BTreeMap::from([(
+            b"op".to_vec(),
+            Bencode::Bytes(b"shutdown".to_vec()),
+        )]));
+        stream.write_all(&encode_bencode(&shutdown)).unwrap();
+        assert!(server.join().unwrap().is_ok());
+        assert!(!std::path::Path::new(&port_file).exists());
+    }
+
+    #[test]
+    fn describe_handshake_sets_session_defaults() {
+        let describe = |client: Bencode| {
+            Bencode::Dict(BTreeMap::from([
+                (b"op".to_vec(), Bencode::Bytes(b"describe".to_vec())),
+                (b"client".to_vec(), client),
+            ]))
+        };
+        let arg = serde_json::json!({ "a": [[2.0]], "b": [[3.0]] });
+        let mut session = Session::default();
+        let mut out = Vec::new();
+        let prefs = Bencode::Dict(BTreeMap::from([(
+            b"format".to_vec(),
+            Bencode::Bytes(b"bencode".to_vec()),
+        )]));
+        handle_message(describe(prefs), &mut session, &mut out).unwrap();
+        assert_eq!(session.value_format, ValueFormat::Bencode);
+        // Only the first describe negotiates.
+        let later = Bencode::Dict(BTreeMap::from([(
+            b"format".to_vec(),
+            Bencode::Bytes(b"json".to_vec()),
+        )]));
+        handle_message(describe(later), &mut session, &mut out).unwrap();
+        out.clear();
+        handle_message(
+            Bencode::Dict(invoke_message("1", "pod.eigs/frobenius-inner", arg.clone())),
+            &mut session,
+            &mut out,
+        )
+        .unwrap();
+        let resp = &decode_all(&out)[0];
+        assert!(matches!(dict_get(resp, "value"), Some(Bencode::Dict(_))));
+
+        // An unsupported preference falls back to json; a size cap applies.
+        let mut session = Session::default();
+        let prefs = Bencode::Dict(BTreeMap::from([
+            (b"format".to_vec(), Bencode::Bytes(b"transit+json".to_vec())),
+            (b"max_message_size".to_vec(), Bencode::Int(40)),
+        ]));
+        handle_message(describe(prefs), &mut session, &mut Vec::new()).unwrap();
+        assert_eq!(session.value_format, ValueFormat::Text);
+        assert_eq!(session.payload, PayloadFormat::Json);
+        let mut out = Vec::new();
+        let big = serde_json::json!({ "a": vec![vec![1.0; 8]; 8], "b": vec![vec![1.0; 8]; 8] });
+        let errors_before = INVOKE_ERROR_COUNT.load(AtomicOrdering::Relaxed);
+        handle_message(
+            Bencode::Dict(invoke_message("2", "pod.eigs/dsum", big)),
+            &mut session,
+            &mut out,
+        )
+        .unwrap();
+        let resp = &decode_all(&out)[0];
+        let msg = bencode_str(dict_get(resp, "ex-message").unwrap()).unwrap();
+        assert!(msg.contains("max_message_size 40"), "{}", msg);
+        assert!(INVOKE_ERROR_COUNT.load(AtomicOrdering::Relaxed) > errors_before);
+    }
+
+    #[test]
+    fn svd_determinant_matches_lu() {
+        let rows = serde_json::json!([[4.0, 1.0, -2.0], [0.5, -3.0, 1.0], [2.0, 1.0, 5.0]]);
+        let det = |method: &str| {
+            let out = invoke_var(
+                "pod.eigs/determinant",
+                &serde_json::json!({ "rows": rows, "method": method }),
+            )
+            .unwrap();
+            out["determinant"].as_f64().unwrap()
+        };
+        let lu = det("lu");
+        assert!(lu < 0.0);
+        assert!((det("svd") - lu).abs() <= 1e-10 * lu.abs());
+        assert!((det("eigen") - lu).abs() <= 1e-10 * lu.abs());
+        assert!(invoke_var(
+            "pod.eigs/determinant",
+            &serde_json::json!({ "rows": rows, "method": "qr" })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn shutdown_is_acknowledged() {
+        let shutdown = Bencode::Dict(BTreeMap::from([
+            (b"op".to_vec(), Bencode::Bytes(b"shutdown".to_vec())),
+            (b"id".to_vec(), Bencode::Bytes(b"9".to_vec())),
+        ]));
+        let mut input = encode_bencode(&shutdown);
+        // Anything after shutdown is never read.
+        input.extend_from_slice(b"d2:op8:describee");
+        let mut out = Vec::new();
+        assert_eq!(
+            serve(&mut input.as_slice(), &mut out).unwrap(),
+            SessionEnd::Shutdown
+        );
+        assert_eq!(out, b"d2:id1:96:statusl4:doneee".to_vec());
+    }
+
+    #[test]
+    fn cond_fro_bounds_two_norm_condition() {
+        let rows = serde_json::json!([[4.0, 1.0, 0.0], [2.0, 3.0, 1.0], [0.0, 1.0, 0.5]]);
+        let out = invoke_var("pod.eigs/cond-fro", &serde_json::json!({ "rows": rows })).unwrap();
+        let cond = out["cond"].as_f64().unwrap();
+        let sv = sorted_singular_values(&parse_rows(&rows, "rows").unwrap());
+        assert!(cond.is_finite());
+        assert!(cond >= sv[0] / sv[sv.len() - 1]);
+
+        let singular = serde_json::json!({ "rows": [[1.0, 2.0], [2.0, 4.0]] });
+        assert!(invoke_var("pod.eigs/cond-fro", &singular).is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        let deep = vec![b'l'; 10_000];
+        match parse_at(&deep, 0) {
+            Err(ParseError::Invalid(msg)) => assert_eq!(msg, "max nesting depth exceeded"),
+            _ => panic!("expected a nesting depth error"),
+        }
+        let mut ok = vec![b'l'; MAX_NESTING_DEPTH];
+        ok.extend(vec![b'e'; MAX_NESTING_DEPTH]);
+        assert!(parse_at(&ok, 0).is_ok());
+
+        let mut out = Vec::new();
+        assert_eq!(
+            serve(&mut deep.as_slice(), &mut out).unwrap(),
+            SessionEnd::Eof
+        );
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn split_spectrum_real_and_imaginary_parts() {
+        let rows = serde_json::json!([[1.0, 3.0, 0.0], [-1.0, 2.0, 4.0], [2.0, 0.0, -1.0]]);
+        let out = invoke_var(
+            "pod.eigs/split-spectrum",
+            &serde_json::json!({ "rows": rows }),
+        )
+        .unwrap();
+        let h = out["symmetric"].as_array().unwrap();
+        assert_eq!(h.len(), 3);
+        assert!(h.iter().all(|l| l.is_f64()));
+        let skew: Vec<[f64; 2]> = serde_json::from_value(out["skew"].clone()).unwrap();
+        assert!(skew.iter().all(|[re, _]| *re == 0.0));
+        // Odd dimension: one zero plus a conjugate pair ±iμ.
+        let mut im: Vec<f64> = skew.iter().map(|[_, im]| *im).collect();
+        im.sort_by(f64::total_cmp);
+        assert!(im[1].abs() < 1e-9);
+        assert!(close_enough(im[0], -im[2]));
+        let k = parse_rows(&rows, "rows").unwrap();
+        let k = (&k - k.transpose()) * 0.5;
+        assert!(close_enough(im[2] * im[2] * 2.0, k.norm_squared()));
+    }
+
+    #[test]
+    fn parse_int_requires_canonical_form() {
+        let parse = |text: &str| parse_at(text.as_bytes(), 0).map(|(v, _)| v);
+        assert!(matches!(parse("i0e"), Ok(Bencode::Int(0))));
+        assert!(matches!(parse("i-13e"), Ok(Bencode::Int(-13))));
+        for (bad, why) in [
+            ("i-0e", "-0"),
+            ("i007e", "leading zero"),
+            ("i-01e", "leading zero"),
+            ("i+5e", "invalid int"),
+            ("ie", "invalid int"),
+            ("i-e", "invalid int"),
+            ("i99999999999999999999e", "out of range"),
+        ] {
+            match parse(bad) {
+                Err(ParseError::Invalid(msg)) => assert!(msg.contains(why), "{}: {}", bad, msg),
+                _ => panic!("{} should be rejected", bad),
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_of_diagonal_and_singular() {
+        let rows = serde_json::json!([[2.0, 0.0, 0.0], [0.0, 4.0, 0.0], [0.0, 0.0, -0.5]]);
+        let out = invoke_var(
+            "pod.eigs/inverse",
+            &serde_json::json!({ "rows": rows, "symmetric": true }),
+        )
+        .unwrap();
+        assert_eq!(
+            out["inverse"],
+            serde_json::json!([[0.5, 0.0, 0.0], [0.0, 0.25, 0.0], [0.0, 0.0, -2.0]])
+        );
+
+        let rank_deficient =
+            serde_json::json!({ "rows": [[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [1.0, 0.0, 1.0]] });
+        assert_eq!(
+            invoke_var("pod.eigs/inverse", &rank_deficient).unwrap_err(),
+            "matrix is singular or ill-conditioned"
+        );
+        let asymmetric = serde_json::json!({ "rows": [[1.0, 2.0], [0.0, 1.0]], "symmetric": true });
+        assert!(invoke_var("pod.eigs/inverse", &asymmetric).is_err());
+    }
+
+    #[test]
+    fn quadratic_form_with_identity_is_squared_norm() {
+        let identity = serde_json::json!([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        let vectors = serde_json::json!([[1.0, 2.0, 2.0], [0.0, -3.0, 4.0], [0.0, 0.0, 0.0]]);
+        let out = invoke_var(
+            "pod.eigs/quadratic-form",
+            &serde_json::json!({ "rows": identity, "vectors": vectors }),
+        )
+        .unwrap();
+        assert_eq!(out["values"], serde_json::json!([9.0, 25.0, 0.0]));
+        let short = serde_json::json!({ "rows": identity, "vectors": [[1.0, 2.0]] });
+        assert!(invoke_var("pod.eigs/quadratic-form", &short).is_err());
+    }
+
+    #[test]
+    fn laplacian_counts_components() {
+        // Two disjoint triangles.
+        let mut adjacency = DMatrix::zeros(6, 6);
+        for (i, j) in [(0, 1), (1, 2), (0, 2), (3, 4), (4, 5), (3, 5)] {
+            adjacency[(i, j)] = 1.0;
+            adjacency[(j, i)] = 1.0;
+        }
+        let input = serde_json::json!({ "rows": matrix_value(&adjacency, Layout::Row) });
+        let out = invoke_var("pod.eigs/laplacian-components", &input).unwrap();
+        assert_eq!(out["num_components"], 2);
+        assert!(out["algebraic_connectivity"].as_f64().unwrap().abs() < 1e-9);
+        assert!(close_enough(out["eigenvalues"][5].as_f64().unwrap(), 3.0));
+
+        // Bridging them leaves one component with a positive Fiedler value.
+        adjacency[(2, 3)] = 1.0;
+        adjacency[(3, 2)] = 1.0;
+        let input = serde_json::json!({ "rows": matrix_value(&adjacency, Layout::Row) });
+        let out = invoke_var("pod.eigs/laplacian-components", &input).unwrap();
+        assert_eq!(out["num_components"], 1);
+        assert!(out["algebraic_connectivity"].as_f64().unwrap() > 0.1);
+
+        // The path 0-1-2 plus an isolated vertex 3, as an oriented incidence
+        // matrix with one column per edge.
+        let incidence = serde_json::json!([[1.0, 0.0], [-1.0, 1.0], [0.0, -1.0], [0.0, 0.0]]);
+        let input = serde_json::json!({ "incidence": incidence });
+        let out = invoke_var("pod.eigs/laplacian-components", &input).unwrap();
+        assert_eq!(out["num_components"], 2);
+        assert!(close_enough(out["eigenvalues"][3].as_f64().unwrap(), 3.0));
+        let both = serde_json::json!({ "incidence": incidence, "rows": [[0.0]] });
+        assert!(invoke_var("pod.eigs/laplacian-components", &both).is_err());
+        let ragged = serde_json::json!({ "incidence": [[1.0, 0.0], [-1.0]] });
+        assert!(invoke_var("pod.eigs/laplacian-components", &ragged).is_err());
+        let empty = serde_json::json!({ "incidence": [] });
+        assert!(invoke_var("pod.eigs/laplacian-components", &empty).is_err());
+    }
+
+    #[test]
+    fn condition_number_field() {
+        let identity = serde_json::json!([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": identity, "condition": true }),
+        )
+        .unwrap();
+        assert!(close_enough(out["condition_number"].as_f64().unwrap(), 1.0));
+
+        let nearly = serde_json::json!([[1.0, 1.0], [1.0, 1.0 + 1.0e-10]]);
+        let input = serde_json::json!({ "rows": nearly, "symmetric": true, "condition": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &input).unwrap();
+        assert!(out["condition_number"].as_f64().unwrap() > 1.0e9);
+
+        let singular = serde_json::json!({ "rows": [[1.0, 0.0], [0.0, 0.0]], "symmetric": true, "condition": true });
+        let out = invoke_var("pod.eigs/eigenvalues", &singular).unwrap();
+        assert_eq!(out["condition_number"], "inf");
+        let plain = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": identity }),
+        )
+        .unwrap();
+        assert!(plain.get("condition_number").is_none());
+    }
+
+    #[test]
+    fn extreme_ints_round_trip() {
+        for (value, text) in [
+            (i64::MAX, "i9223372036854775807e"),
+            (i64::MIN, "i-9223372036854775808e"),
+        ] {
+            let bytes = encode_bencode(&Bencode::Int(value));
+            assert_eq!(bytes, text.as_bytes());
+            let (parsed, used) = parse_at(&bytes, 0).unwrap();
+            assert!(matches!(parsed, Bencode::Int(v) if v == value));
+            assert_eq!(used, bytes.len());
+            assert_eq!(encode_bencode(&parsed), bytes);
+        }
+        // One past either end is out of range rather than wrapped.
+        assert!(parse_at(b"i9223372036854775808e", 0).is_err());
+        assert!(parse_at(b"i-9223372036854775809e", 0).is_err());
+
+        // An integer id is echoed back byte-exactly.
+        let mut msg = invoke_message(
+            "x",
+            "pod.eigs/frobenius-inner",
+            serde_json::json!({ "a": [[1.0]], "b": [[1.0]] }),
+        );
+        msg.insert(b"id".to_vec(), Bencode::Int(i64::MIN));
+        let mut out = Vec::new();
+        handle_invoke(&msg, &Session::default(), &mut out).unwrap();
+        let resp = &decode_all(&out)[0];
+        assert!(matches!(dict_get(resp, "id"), Some(Bencode::Int(i64::MIN))));
+    }
+
+    #[test]
+    fn bracket_eigenvalues_sum_to_zero() {
+        let a = serde_json::json!([[1.0, 2.0, 0.0], [0.0, -1.0, 3.0], [1.0, 0.0, 2.0]]);
+        let b = serde_json::json!([[0.0, 1.0, 1.0], [2.0, 0.5, 0.0], [-1.0, 0.0, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/bracket-eigs",
+            &serde_json::json!({ "a": a, "b": b }),
+        )
+        .unwrap();
+        assert_eq!(out["traceless"], true);
+        let sum: Vec<f64> = serde_json::from_value(out["eigenvalue_sum"].clone()).unwrap();
+        assert!(sum[0].abs() < 1e-9 && sum[1].abs() < 1e-9);
+        assert_eq!(out["eigenvalues"].as_array().unwrap().len(), 3);
+
+        let out = invoke_var(
+            "pod.eigs/bracket-eigs",
+            &serde_json::json!({ "a": a, "b": a }),
+        )
+        .unwrap();
+        assert_eq!(
+            out["eigenvalues"],
+            serde_json::json!([[0.0, 0.0], [0.0, 0.0], [0.0, 0.0]])
+        );
+    }
+
+    #[cfg(feature = "edn")]
+    #[test]
+    fn edn_round_trip() {
+        let value = edn::parse(
+            r#"[{:rows [[1 2] [3 4.5]], :symmetric false, :ratio 1/4, "k" nil} ; note
+            #{:a} (1 -2)]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                { "rows": [[1, 2], [3, 4.5]], "symmetric": false, "ratio": 0.25, "k": null },
+                ["a"],
+                [1, -2]
+            ])
+        );
+        let text = edn::write(
+            &serde_json::json!({ "eigenvalues": [1.0, 2.5], "warning": "a \"b\"", "two words": 1 }),
+        );
+        assert_eq!(
+            text,
+            r#"{:eigenvalues [1.0 2.5], "two words" 1, :warning "a \"b\""}"#
+        );
+        assert_eq!(
+            edn::parse(&text).unwrap()["eigenvalues"],
+            serde_json::json!([1.0, 2.5])
+        );
+        assert!(edn::parse("[1 2").is_err());
+        assert!(edn::parse("{:a}").is_err());
+        assert_eq!(
+            edn::parse(r#"{:at #inst "2020-01-01"}"#).unwrap_err(),
+            "unsupported tag #inst at byte 5"
+        );
+        assert!(edn::parse("[#uuid \"00000000-0000-0000-0000-000000000000\"]").is_err());
+    }
+
+    #[cfg(feature = "edn")]
+    #[test]
+    fn edn_preference_from_describe() {
+        let describe = Bencode::Dict(BTreeMap::from([
+            (b"op".to_vec(), Bencode::Bytes(b"describe".to_vec())),
+            (
+                b"client".to_vec(),
+                Bencode::Dict(BTreeMap::from([(
+                    b"format".to_vec(),
+                    Bencode::Bytes(b"edn".to_vec()),
+                )])),
+            ),
+        ]));
+        let mut session = Session::default();
+        let mut out = Vec::new();
+        handle_message(describe, &mut session, &mut out).unwrap();
+        assert_eq!(
+            dict_get(&decode_all(&out)[0], "format")
+                .and_then(bencode_str)
+                .as_deref(),
+            Some("edn")
+        );
+
+        let mut msg = invoke_message("1", "pod.eigs/frobenius-inner", Value::Null);
+        msg.insert(
+            b"args".to_vec(),
+            Bencode::Bytes(b"[{:a [[2]] :b [[3]]}]".to_vec()),
+        );
+        let mut out = Vec::new();
+        handle_message(Bencode::Dict(msg), &mut session, &mut out).unwrap();
+        let value = bencode_str(dict_get(&decode_all(&out)[0], "value").unwrap()).unwrap();
+        assert_eq!(value, "{:inner 6.0}");
+    }
+
+    #[cfg(feature = "edn")]
+    #[test]
+    fn edn_nesting_is_bounded() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(edn::parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert_eq!(
+            edn::parse(&nested(MAX_NESTING_DEPTH + 1)).unwrap_err(),
+            "nesting too deep"
+        );
+
+        // Deep enough to overflow the stack if the reader recursed.
+        let session = Session {
+            payload: PayloadFormat::Edn,
+            ..Session::default()
+        };
+        let mut msg = invoke_message("1", "pod.eigs/eigenvalues", Value::Null);
+        msg.insert(
+            b"args".to_vec(),
+            Bencode::Bytes(nested(1_000_000).into_bytes()),
+        );
+        let mut out = Vec::new();
+        handle_invoke(&msg, &session, &mut out).unwrap();
+        let err = bencode_str(dict_get(&decode_all(&out)[0], "ex-message").unwrap()).unwrap();
+        assert!(err.contains("nesting too deep"), "{}", err);
+    }
+
+    #[test]
+    fn payload_format_selection() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(payload_format(&args(&[]), None), Ok(PayloadFormat::Json));
+        assert_eq!(
+            payload_format(&args(&["--format", "json"]), Some("xml")),
+            Ok(PayloadFormat::Json)
+        );
+        assert!(payload_format(&args(&[]), Some("xml")).is_err());
+        assert!(payload_format(&args(&["--format"]), None).is_err());
+        #[cfg(feature = "edn")]
+        assert_eq!(
+            payload_format(&args(&[]), Some("edn")),
+            Ok(PayloadFormat::Edn)
+        );
+    }
+
+    #[test]
+    fn stationary_two_state_chain() {
+        // P = [[1-a, a], [b, 1-b]] has π = (b, a) / (a + b).
+        let (a, b) = (0.3, 0.1);
+        let rows = serde_json::json!([[1.0 - a, a], [b, 1.0 - b]]);
+        let out = invoke_var("pod.eigs/stationary", &serde_json::json!({ "rows": rows })).unwrap();
+        let pi: Vec<f64> = serde_json::from_value(out["stationary"].clone()).unwrap();
+        assert!(close_enough(pi[0], b / (a + b)) && close_enough(pi[1], a / (a + b)));
+        assert_eq!(out["eigenvalue_one_multiplicity"], 1);
+        assert!(out.get("warning").is_none());
+
+        let cols = serde_json::json!([[1.0 - a, b], [a, 1.0 - b]]);
+        let out = invoke_var(
+            "pod.eigs/stationary",
+            &serde_json::json!({ "rows": cols, "stochastic": "column" }),
+        )
+        .unwrap();
+        assert!(close_enough(out["stationary"][0].as_f64().unwrap(), 0.25));
+
+        let counts = serde_json::json!([[7.0, 3.0], [1.0, 9.0]]);
+        assert!(invoke_var(
+            "pod.eigs/stationary",
+            &serde_json::json!({ "rows": counts })
+        )
+        .is_err());
+        let out = invoke_var(
+            "pod.eigs/stationary",
+            &serde_json::json!({ "rows": counts, "normalize": true }),
+        )
+        .unwrap();
+        assert!(close_enough(out["stationary"][1].as_f64().unwrap(), 0.75));
+        let arg =
+            serde_json::json!({ "rows": counts, "normalize": true, "output_layout": "column" });
+        let out = invoke_var("pod.eigs/stationary", &arg).unwrap();
+        assert_eq!(out["matrix"], serde_json::json!([[0.7, 0.1], [0.3, 0.9]]));
+
+        let reducible = serde_json::json!([[1.0, 0.0], [0.0, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/stationary",
+            &serde_json::json!({ "rows": reducible }),
+        )
+        .unwrap();
+        assert_eq!(out["eigenvalue_one_multiplicity"], 2);
+        assert!(out["warning"].as_str().unwrap().contains("reducible"));
+    }
+
+    #[test]
+    fn stats_op_reports_parse_errors() {
+        let mut input = io::Cursor::new(b"xd2:id1:72:op5:statse".to_vec());
+        let mut out = Vec::new();
+        serve(&mut input, &mut out).unwrap();
+        let msgs = decode_all(&out);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(
+            dict_get(&msgs[0], "id").and_then(bencode_str).as_deref(),
+            Some("7")
+        );
+        assert!(matches!(dict_get(&msgs[0], "parse_errors"), Some(Bencode::Int(n)) if *n >= 1));
+        assert!(matches!(
+            dict_get(&msgs[0], "invokes"),
+            Some(Bencode::Int(_))
+        ));
+        assert!(dict_get(&msgs[0], "last_error").is_some());
+    }
+
+    #[test]
+    fn eig_sweep_timeout_returns_partial_results() {
+        let a = serde_json::json!([[2.0, 1.0], [1.0, 2.0]]);
+        let b = serde_json::json!([[1.0, 0.0], [0.0, -1.0]]);
+        let ts = vec![0.0, 0.25, 0.5, 0.75, 1.0];
+        let input = serde_json::json!({ "a": a, "b": b, "ts": ts, "symmetric": true, "batch_timeout_ms": 0 });
+        let out = invoke_var("pod.eigs/eig-sweep", &input).unwrap();
+        assert_eq!(out["results"].as_array().unwrap().len(), 1);
+        assert_eq!(out["results"][0]["t"], 0.0);
+        assert_eq!(out["remaining"], 4);
+
+        let input = serde_json::json!({ "a": a, "b": b, "ts": ts, "batch_timeout_ms": 60_000 });
+        let out = invoke_var("pod.eigs/eig-sweep", &input).unwrap();
+        assert_eq!(out["results"].as_array().unwrap().len(), 5);
+        assert_eq!(out["remaining"], 0);
+    }
+
+    #[test]
+    fn trace_and_rank() {
+        let identity = |n: usize| {
+            DMatrix::<f64>::identity(n, n)
+                .row_iter()
+                .map(|r| r.iter().cloned().collect())
+                .collect::<Vec<Vec<f64>>>()
+        };
+        let out = invoke_var(
+            "pod.eigs/trace",
+            &serde_json::json!({ "rows": identity(6) }),
+        )
+        .unwrap();
+        assert_eq!(out["trace"], 6.0);
+        let out = invoke_var("pod.eigs/rank", &serde_json::json!({ "rows": identity(6) })).unwrap();
+        assert_eq!(out["rank"], 6);
+        let out = invoke_var(
+            "pod.eigs/rank",
+            &serde_json::json!({ "rows": vec![vec![0.0; 3]; 3] }),
+        )
+        .unwrap();
+        assert_eq!(out["rank"], 0);
+
+        let nearly_singular = serde_json::json!([[1.0, 0.0], [0.0, 1.0e-6]]);
+        let out = invoke_var(
+            "pod.eigs/rank",
+            &serde_json::json!({ "rows": nearly_singular }),
+        )
+        .unwrap();
+        assert_eq!(out["rank"], 2);
+        let out = invoke_var(
+            "pod.eigs/rank",
+            &serde_json::json!({ "rows": nearly_singular, "rank_tol": 1.0e-3 }),
+        )
+        .unwrap();
+        assert_eq!(out["rank"], 1);
+
+        let wide = serde_json::json!([[1.0, 2.0, 3.0, 4.0], [2.0, 4.0, 6.0, 8.0]]);
+        let out = invoke_var("pod.eigs/rank", &serde_json::json!({ "rows": wide })).unwrap();
+        assert_eq!(out["rank"], 1);
+    }
+
+    #[test]
+    fn non_finite_entries_are_rejected() {
+        let mut matrix = DMatrix::<f64>::identity(3, 3);
+        matrix[(1, 2)] = 1.0e308 * 10.0;
+        assert_eq!(
+            ensure_finite(matrix).unwrap_err(),
+            "matrix contains non-finite entry at (1,2)"
+        );
+        let mut matrix = DMatrix::<f64>::identity(2, 2);
+        matrix[(0, 1)] = f64::NAN;
+        assert!(ensure_finite(matrix).is_err());
+
+        // Over the wire an overflowing literal is already rejected by the
+        // payload reader, so `ensure_finite` is only a backstop.
+        let mut msg = invoke_message("1", "pod.eigs/eigenvalues", Value::Null);
+        msg.insert(
+            b"args".to_vec(),
+            Bencode::Bytes(br#"[{"rows": [[1, 1e400], [0, 1]]}]"#.to_vec()),
+        );
+        let mut out = Vec::new();
+        handle_invoke(&msg, &Session::default(), &mut out).unwrap();
+        let reply = decode_all(&out);
+        assert_eq!(
+            dict_get(&reply[0], "ex-message")
+                .and_then(bencode_str)
+                .as_deref(),
+            Some("invalid json input")
+        );
+        #[cfg(feature = "edn")]
+        assert_eq!(
+            edn::parse("[{:rows [[1 1e400] [0 1]]}]").unwrap_err(),
+            "invalid number \"1e400\""
+        );
+    }
+
+    #[test]
+    fn batch_invoke_preserves_order() {
+        let batch = serde_json::json!([
+            { "rows": [[2.0, 0.0], [0.0, 3.0]], "symmetric": true },
+            { "rows": [[5.0]] }
+        ]);
+        let mut out = Vec::new();
+        handle_invoke(
+            &invoke_message("1", "pod.eigs/eigenvalues", batch),
+            &Session::default(),
+            &mut out,
+        )
+        .unwrap();
+        let value: Value = serde_json::from_str(
+            &bencode_str(dict_get(&decode_all(&out)[0], "value").unwrap()).unwrap(),
+        )
+        .unwrap();
+        let results = value["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["eigenvalues"], serde_json::json!([2.0, 3.0]));
+        assert_eq!(results[1]["eigenvalues"][0][0], 5.0);
+
+        // A zero budget on the first map still computes that item and
+        // reports the rest as remaining, sequentially and in parallel.
+        for n in [3, PARALLEL_BATCH_MIN + 2] {
+            let mut items = vec![serde_json::json!({ "rows": [[5.0]] }); n];
+            items[0]["batch_timeout_ms"] = serde_json::json!(0);
+            let mut out = Vec::new();
+            handle_invoke(
+                &invoke_message("3", "pod.eigs/eigenvalues", Value::Array(items)),
+                &Session::default(),
+                &mut out,
+            )
+            .unwrap();
+            let value: Value = serde_json::from_str(
+                &bencode_str(dict_get(&decode_all(&out)[0], "value").unwrap()).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(value["results"].as_array().unwrap().len(), 1);
+            assert_eq!(value["remaining"], n - 1);
+        }
+
+        // A single map keeps its unbatched shape.
+        let single = serde_json::json!({ "rows": [[5.0]], "symmetric": true });
+        let mut out = Vec::new();
+        handle_invoke(
+            &invoke_message("2", "pod.eigs/eigenvalues", single),
+            &Session::default(),
+            &mut out,
+        )
+        .unwrap();
+        let value: Value = serde_json::from_str(
+            &bencode_str(dict_get(&decode_all(&out)[0], "value").unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(value["eigenvalues"], serde_json::json!([5.0]));
+    }
+
+    #[test]
+    fn count_in_region_by_both_methods() {
+        // Eigenvalues 1, 2, 5 and the pair 3 ± 2i.
+        let rows = serde_json::json!([
+            [1.0, 0.5, 0.0, 0.0, 0.2],
+            [0.0, 2.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 3.0, 2.0, 0.0],
+            [0.0, 0.0, -2.0, 3.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 5.0]
+        ]);
+        let count = |region: Value, method: &str| {
+            let mut input = region;
+            input["rows"] = rows.clone();
+            input["method"] = serde_json::json!(method);
+            invoke_var("pod.eigs/count-in-region", &input).unwrap()
+        };
+        for method in ["eigenvalues", "contour"] {
+            let disk = count(serde_json::json!({ "center": 1.5, "radius": 1.0 }), method);
+            assert_eq!(disk["count"], 2, "{}", method);
+            let upper = count(
+                serde_json::json!({ "re": [2.5, 5.5], "im": [0.5, 3.0] }),
+                method,
+            );
+            assert_eq!(upper["count"], 1, "{}", method);
+            let strip = count(
+                serde_json::json!({ "re": [0.5, 5.5], "im": [-2.5, 2.5] }),
+                method,
+            );
+            assert_eq!(strip["count"], 5, "{}", method);
+        }
+        let out = count(
+            serde_json::json!({ "center": [3.0, 2.0], "radius": 0.5 }),
+            "contour",
+        );
+        assert!((out["integral"][0].as_f64().unwrap() - 1.0).abs() < 1.0e-6);
+        assert!(invoke_var(
+            "pod.eigs/count-in-region",
+            &serde_json::json!({ "rows": rows, "radius": 1.0, "re": [0, 1] })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn masked_symmetrize_keeps_trusted_entries() {
+        let rows = serde_json::json!([[1.0, 2.0, 0.0], [4.0, 5.0, 7.0], [1.0, 3.0, 9.0]]);
+        let mask = serde_json::json!([
+            [true, false, false],
+            [false, true, true],
+            [false, false, true]
+        ]);
+        let out = invoke_var(
+            "pod.eigs/masked-symmetrize",
+            &serde_json::json!({ "rows": rows, "mask": mask }),
+        )
+        .unwrap();
+        assert_eq!(
+            out["symmetrized"],
+            serde_json::json!([[1.0, 3.0, 0.5], [3.0, 5.0, 7.0], [0.5, 7.0, 9.0]])
+        );
+
+        let conflicting = serde_json::json!([
+            [true, true, false],
+            [true, true, false],
+            [false, false, true]
+        ]);
+        assert!(invoke_var(
+            "pod.eigs/masked-symmetrize",
+            &serde_json::json!({ "rows": rows, "mask": conflicting })
+        )
+        .is_err());
+        let short = serde_json::json!([[true, true], [true, true]]);
+        assert!(invoke_var(
+            "pod.eigs/masked-symmetrize",
+            &serde_json::json!({ "rows": rows, "mask": short })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn parallel_batch_keeps_order_and_per_item_errors() {
+        let mut batch: Vec<Value> = (0..12)
+            .map(|k| serde_json::json!({ "rows": [[k as f64, 0.0], [0.0, -(k as f64)]], "symmetric": true }))
+            .collect();
+        batch[5] = serde_json::json!({ "rows": [[1.0, 2.0]] });
+        let mut out = Vec::new();
+        handle_invoke(
+            &invoke_message("1", "pod.eigs/eigenvalues", Value::Array(batch)),
+            &Session::default(),
+            &mut out,
+        )
+        .unwrap();
+        let value: Value = serde_json::from_str(
+            &bencode_str(dict_get(&decode_all(&out)[0], "value").unwrap()).unwrap(),
+        )
+        .unwrap();
+        let results = value["results"].as_array().unwrap();
+        assert_eq!(results.len(), 12);
+        for (k, result) in results.iter().enumerate() {
+            if k == 5 {
+                assert!(result["error"].as_str().unwrap().contains("square"));
+            } else {
+                assert_eq!(
+                    result["eigenvalues"],
+                    serde_json::json!([-(k as f64), k as f64])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stats_reflect_invokes() {
+        let stats = |session: &mut Session| {
+            let mut out = Vec::new();
+            let msg = BTreeMap::from([(b"op".to_vec(), Bencode::Bytes(b"stats".to_vec()))]);
+            handle_message(Bencode::Dict(msg), session, &mut out).unwrap();
+            let resp = decode_all(&out).remove(0);
+            let get = |key: &str| match dict_get(&resp, key) {
+                Some(Bencode::Int(n)) => *n,
+                other => panic!("{} missing: {:?}", key, other),
+            };
+            (
+                get("messages"),
+                get("invokes"),
+                get("invoke_errors"),
+                get("errors"),
+                get("uptime_ms"),
+            )
+        };
+        let mut session = Session::default();
+        let before = stats(&mut session);
+        let arg = serde_json::json!({ "a": [[2.0]], "b": [[3.0]] });
+        for id in ["1", "2"] {
+            handle_message(
+                Bencode::Dict(invoke_message(id, "pod.eigs/frobenius-inner", arg.clone())),
+                &mut session,
+                &mut Vec::new(),
+            )
+            .unwrap();
+        }
+        handle_message(
+            Bencode::Dict(invoke_message("3", "pod.eigs/frobenius-inner", Value::Null)),
+            &mut session,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        let after = stats(&mut session);
+        assert!(after.0 >= before.0 + 4);
+        assert!(after.1 >= before.1 + 3);
+        assert!(after.2 > before.2);
+        assert!(after.3 >= after.2);
+        assert!(after.4 >= 0);
+    }
+
+    #[test]
+    fn many_messages_in_one_read_are_all_handled() {
+        // Every `read` fills the chunk, so many messages (and a stray byte)
+        // arrive together and some straddle chunk boundaries.
+        struct Backlog(Vec<u8>);
+        impl Read for Backlog {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = self.0.len().min(buf.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0.drain(..n);
+                Ok(n)
+            }
+        }
+        let mut input = Vec::new();
+        for k in 0..500 {
+            let msg = invoke_message(
+                &k.to_string(),
+                "pod.eigs/frobenius-inner",
+                serde_json::json!({ "a": [[k]], "b": [[1]] }),
+            );
+            input.extend(encode_bencode(&Bencode::Dict(msg)));
+            if k == 250 {
+                input.push(b'x');
+            }
+        }
+        let mut out = Vec::new();
+        assert!(matches!(
+            serve(&mut Backlog(input), &mut out).unwrap(),
+            SessionEnd::Eof
+        ));
+        let msgs = decode_all(&out);
+        assert_eq!(msgs.len(), 1000);
+        for (k, pair) in msgs.chunks(2).enumerate() {
+            assert_eq!(
+                dict_get(&pair[0], "id").and_then(bencode_str),
+                Some(k.to_string())
+            );
+            let value = bencode_str(dict_get(&pair[0], "value").unwrap()).unwrap();
+            assert_eq!(value, format!("{{\"inner\":{}.0}}", k));
+        }
+    }
+
+    #[test]
+    fn interpolate_endpoints() {
+        let a = serde_json::json!([[4.0, 1.0], [1.0, 3.0]]);
+        let b = serde_json::json!([[2.0, -0.5], [-0.5, 1.0]]);
+        let target = |t: f64| if t == 0.0 { &a } else { &b };
+        for mode in ["linear", "log-euclidean"] {
+            for t in [0.0, 1.0] {
+                let out = invoke_var(
+                    "pod.eigs/interpolate",
+                    &serde_json::json!({ "a": a, "b": b, "t": t, "mode": mode }),
+                )
+                .unwrap();
+                for i in 0..2 {
+                    for j in 0..2 {
+                        let got = out["matrix"][i][j].as_f64().unwrap();
+                        assert!(
+                            close_enough(got, target(t)[i][j].as_f64().unwrap()),
+                            "{} t={}",
+                            mode,
+                            t
+                        );
+                    }
+                }
+            }
+        }
+        // Halfway between diag(1, 4) and diag(4, 1) the log-Euclidean mean is 2I.
+        let a = serde_json::json!([[1.0, 0.0], [0.0, 4.0]]);
+        let b = serde_json::json!([[4.0, 0.0], [0.0, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/interpolate",
+            &serde_json::json!({ "a": a, "b": b, "t": 0.5, "mode": "log-euclidean" }),
+        )
+        .unwrap();
+        assert!(close_enough(out["matrix"][0][0].as_f64().unwrap(), 2.0));
+        let indefinite = serde_json::json!([[1.0, 2.0], [2.0, 1.0]]);
+        assert!(invoke_var(
+            "pod.eigs/interpolate",
+            &serde_json::json!({ "a": indefinite, "b": b, "t": 0.5, "mode": "log-euclidean" })
+        )
+        .is_err());
+        assert!(invoke_var(
+            "pod.eigs/interpolate",
+            &serde_json::json!({ "a": a, "b": b, "t": 1.5 })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn collapse_real_flattens_only_real_spectra() {
+        let eig = |rows: Value| {
+            invoke_var(
+                "pod.eigs/eigenvalues",
+                &serde_json::json!({ "rows": rows, "collapse_real": true }),
+            )
+            .unwrap()
+        };
+        let upper = eig(serde_json::json!([
+            [1.0, 5.0, -2.0],
+            [0.0, 3.0, 4.0],
+            [0.0, 0.0, 2.0]
+        ]));
+        let values: Vec<f64> = serde_json::from_value(upper["eigenvalues"].clone()).unwrap();
+        assert_eq!(values.len(), 3);
+        for expected in [1.0, 2.0, 3.0] {
+            assert!(values.iter().any(|v| close_enough(*v, expected)));
+        }
+        let rotation = eig(serde_json::json!([
+            [0.0, -1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0]
+        ]));
+        assert!(rotation["eigenvalues"][0].is_array());
+        assert_eq!(rotation["eigenvalues"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn svd_of_diagonal_matrix() {
+        let rows = serde_json::json!([[-2.0, 0.0, 0.0], [0.0, 5.0, 0.0], [0.0, 0.0, 0.5]]);
+        let out = invoke_var(
+            "pod.eigs/svd",
+            &serde_json::json!({ "rows": rows, "values_only": true }),
+        )
+        .unwrap();
+        assert_eq!(out["singular_values"], serde_json::json!([5.0, 2.0, 0.5]));
+        assert!(out.get("u").is_none());
+
+        let out = invoke_var("pod.eigs/svd", &serde_json::json!({ "rows": rows })).unwrap();
+        let sigma: Vec<f64> = serde_json::from_value(out["singular_values"].clone()).unwrap();
+        assert_eq!(sigma, vec![5.0, 2.0, 0.5]);
+        let read = |key: &str| {
+            let rows: Vec<Vec<f64>> = serde_json::from_value(out[key].clone()).unwrap();
+            DMatrix::from_fn(3, 3, |i, j| rows[i][j])
+        };
+        let (u, v) = (read("u"), read("v"));
+        let rebuilt = &u * DMatrix::from_diagonal(&DVector::from_vec(sigma)) * v.transpose();
+        let original =
+            DMatrix::from_row_slice(3, 3, &[-2.0, 0.0, 0.0, 0.0, 5.0, 0.0, 0.0, 0.0, 0.5]);
+        assert!((rebuilt - original).norm() < 1.0e-12);
+    }
+
+    #[test]
+    fn svd_accepts_rectangular_rows() {
+        let rows = vec![vec![3.0, 0.0, 0.0, 0.0], vec![0.0, -4.0, 0.0, 0.0]];
+        let out = invoke_var("pod.eigs/svd", &serde_json::json!({ "rows": rows })).unwrap();
+        let sigma: Vec<f64> = serde_json::from_value(out["singular_values"].clone()).unwrap();
+        assert_eq!(sigma, vec![4.0, 3.0]);
+        let u: Vec<Vec<f64>> = serde_json::from_value(out["u"].clone()).unwrap();
+        let v: Vec<Vec<f64>> = serde_json::from_value(out["v"].clone()).unwrap();
+        assert_eq!((u.len(), u[0].len()), (2, 2));
+        assert_eq!((v.len(), v[0].len()), (4, 2));
+        let u = DMatrix::from_fn(2, 2, |i, j| u[i][j]);
+        let v = DMatrix::from_fn(4, 2, |i, j| v[i][j]);
+        let rebuilt = &u * DMatrix::from_diagonal(&DVector::from_vec(sigma)) * v.transpose();
+        let original = DMatrix::from_fn(2, 4, |i, j| rows[i][j]);
+        assert!((rebuilt - original).norm() < 1.0e-12);
+    }
+
+    #[test]
+    fn deflated_eigs_drop_known_modes() {
+        // Eigenpairs: 1 for (1, -1, 0), 3 for (1, 1, 0), 7 for (0, 0, 1).
+        let rows = serde_json::json!([[2.0, 1.0, 0.0], [1.0, 2.0, 0.0], [0.0, 0.0, 7.0]]);
+        let out = invoke_var(
+            "pod.eigs/deflated-eigs",
+            &serde_json::json!({ "rows": rows, "deflate": [[2.0, 2.0, 0.0]] }),
+        )
+        .unwrap();
+        let values: Vec<f64> = serde_json::from_value(out["eigenvalues"].clone()).unwrap();
+        assert_eq!(values.len(), 2);
+        assert!(close_enough(values[0], 1.0) && close_enough(values[1], 7.0));
+
+        let dependent = serde_json::json!([[1.0, 1.0, 0.0], [2.0, 2.0, 0.0]]);
+        assert!(invoke_var(
+            "pod.eigs/deflated-eigs",
+            &serde_json::json!({ "rows": rows, "deflate": dependent })
+        )
+        .is_err());
+        assert!(invoke_var(
+            "pod.eigs/deflated-eigs",
+            &serde_json::json!({ "rows": rows, "deflate": [[1.0, 0.0]] })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn shutdown_is_handled_exactly_once() {
+        let op = |name: &str| {
+            Bencode::Dict(BTreeMap::from([(
+                b"op".to_vec(),
+                Bencode::Bytes(name.as_bytes().to_vec()),
+            )]))
+        };
+        let mut session = Session::default();
+        assert_eq!(
+            handle_message(op("describe"), &mut session, &mut Vec::new()).unwrap(),
+            Flow::Continue
+        );
+        let mut out = Vec::new();
+        assert_eq!(
+            handle_message(op("shutdown"), &mut session, &mut out).unwrap(),
+            Flow::Shutdown
+        );
+        assert_eq!(decode_all(&out).len(), 1);
+
+        let mut input = encode_bencode(&op("shutdown"));
+        input.extend(encode_bencode(&op("shutdown")));
+        let mut out = Vec::new();
+        assert_eq!(
+            serve(&mut input.as_slice(), &mut out).unwrap(),
+            SessionEnd::Shutdown
+        );
+        assert_eq!(decode_all(&out).len(), 1);
+    }
+
+    #[test]
+    fn schur_diagnostics_only_when_requested() {
+        let rows = serde_json::json!([[4.0, -2.0, 1.0], [3.0, 6.0, -4.0], [2.0, 1.0, 8.0]]);
+        let out = invoke_var("pod.eigs/eigenvalues", &serde_json::json!({ "rows": rows })).unwrap();
+        assert!(out.get("diagnostics").is_none());
+
+        let out = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "diagnostics": true }),
+        )
+        .unwrap();
+        let diagnostics = &out["diagnostics"];
+        assert_eq!(diagnostics["converged"], true);
+        assert_eq!(diagnostics["max_iter"], SCHUR_MAX_ITER);
+        let min_iter = diagnostics["min_iter"].as_u64().unwrap() as usize;
+        assert!((1..=SCHUR_MAX_ITER).contains(&min_iter));
+        let matrix = parse_rows(&rows, "rows").unwrap();
+        assert!(Schur::try_new(matrix.clone(), SCHUR_EPS, min_iter).is_some());
+        if min_iter > 1 {
+            assert!(Schur::try_new(matrix, SCHUR_EPS, min_iter - 1).is_none());
+        }
+        assert!(invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "diagnostics": true, "symmetric": true })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn similarity_transform_preserves_eigenvalues() {
+        let a = serde_json::json!([[1.0, 2.0, 0.0], [-2.0, 1.0, 0.5], [0.0, 0.3, 4.0]]);
+        let p = serde_json::json!([[2.0, 1.0, 0.0], [0.0, 1.0, 3.0], [1.0, 0.0, 1.0]]);
+        let out = invoke_var(
+            "pod.eigs/transform-eigs",
+            &serde_json::json!({ "a": a, "p": p }),
+        )
+        .unwrap();
+        assert_eq!(out["eigenvalues"].as_array().unwrap().len(), 3);
+        assert!(out["max_discrepancy"].as_f64().unwrap() < 1.0e-9);
+
+        let singular = serde_json::json!([[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 0.0, 1.0]]);
+        let err = invoke_var(
+            "pod.eigs/transform-eigs",
+            &serde_json::json!({ "a": a, "p": singular }),
+        )
+        .unwrap_err();
+        assert!(err.contains("singular"));
+    }
+
+    #[test]
+    fn strict_parse_rejects_unsorted_dict_keys() {
+        let sorted = b"d2:id1:12:op8:describee";
+        let unsorted = b"d2:op8:describe2:id1:1e";
+        let nested = b"d4:argsd1:bi1e1:ai2eee";
+        let duplicate = b"d1:ai1e1:ai2ee";
+        for strict in [false, true] {
+            assert!(parse_with(sorted, 0, strict).is_ok());
+        }
+        let reencoded = encode_bencode(&parse_with(unsorted, 0, false).unwrap().0);
+        assert_eq!(reencoded, sorted.to_vec());
+        for bad in [&unsorted[..], &nested[..], &duplicate[..]] {
+            assert!(parse_with(bad, 0, false).is_ok());
+            match parse_with(bad, 0, true) {
+                Err(ParseError::Invalid(msg)) => assert_eq!(msg, "dict keys not sorted"),
+                other => panic!("expected an invalid parse, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn ping_answers_immediately() {
+        let ping = Bencode::Dict(BTreeMap::from([
+            (b"op".to_vec(), Bencode::Bytes(b"ping".to_vec())),
+            (b"id".to_vec(), Bencode::Bytes(b"p1".to_vec())),
+        ]));
+        let mut out = Vec::new();
+        assert_eq!(
+            handle_message(ping, &mut Session::default(), &mut out).unwrap(),
+            Flow::Continue
+        );
+        assert_eq!(out, b"d2:id2:p12:op4:ping6:statusl4:doneee".to_vec());
+    }
+
+    #[test]
+    fn real_jordan_detects_a_two_by_two_block() {
+        // A 2×2 Jordan block for 3 plus a simple eigenvalue 5, hidden by a similarity.
+        let j = DMatrix::from_row_slice(3, 3, &[3.0, 1.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 5.0]);
+        let p = DMatrix::from_row_slice(3, 3, &[1.0, 2.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0]);
+        let a = &p * j * p.clone().try_inverse().unwrap();
+        let rows: Vec<Vec<f64>> = a.row_iter().map(|r| r.iter().cloned().collect()).collect();
+        let out = invoke_var("pod.eigs/real-jordan", &serde_json::json!({ "rows": rows })).unwrap();
+        assert_eq!(out["defective"], true);
+        let groups = out["groups"].as_array().unwrap();
+        let three = groups
+            .iter()
+            .find(|g| (g["eigenvalue"][0].as_f64().unwrap() - 3.0).abs() < 1.0e-3)
+            .unwrap();
+        assert_eq!(three["algebraic"], 2);
+        assert_eq!(three["geometric"], 1);
+        assert_eq!(three["block_sizes"], serde_json::json!([2]));
+        assert!(out["form"].is_array());
+
+        // A rotation is diagonalizable over C: one conjugate pair with a 1×1 block.
+        let rotation = serde_json::json!([[0.0, -2.0], [2.0, 0.0]]);
+        let out = invoke_var(
+            "pod.eigs/real-jordan",
+            &serde_json::json!({ "rows": rotation }),
+        )
+        .unwrap();
+        assert_eq!(out["defective"], false);
+        assert_eq!(out["groups"].as_array().unwrap().len(), 1);
+        assert_eq!(out["groups"][0]["conjugate_pair"], true);
+        assert_eq!(out["groups"][0]["confidence"], "high");
+
+        let zero = serde_json::json!({ "rows": vec![vec![0.0; 3]; 3] });
+        let out = invoke_var("pod.eigs/real-jordan", &zero).unwrap();
+        assert_eq!(out["groups"].as_array().unwrap().len(), 1);
+        assert_eq!(out["groups"][0]["algebraic"], 3);
+    }
+
+    #[test]
+    fn real_jordan_mirrors_near_conjugate_clusters() {
+        // Eigenvalues 2, 2 ± 0.12i and 2 ± 0.30i with a clustering tolerance
+        // of ~0.2: clustering the whole plane greedily groups
+        // {2 − 0.30i, 2 − 0.12i}, {2, 2 + 0.12i} and {2 + 0.30i}, which
+        // accounts for only four of the five eigenvalues.
+        let rows = serde_json::json!([
+            [2.0, 0.12, 0.0, 0.0, 0.0],
+            [-0.12, 2.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 2.0, 0.30, 0.0],
+            [0.0, 0.0, -0.30, 2.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0, 2.0]
+        ]);
+        let out = invoke_var(
+            "pod.eigs/real-jordan",
+            &serde_json::json!({ "rows": rows, "cluster_tol": 0.1 }),
+        )
+        .unwrap();
+        let groups = out["groups"].as_array().unwrap();
+        let accounted: u64 = groups
+            .iter()
+            .map(|g| {
+                let width = if g["conjugate_pair"] == true { 2 } else { 1 };
+                g["algebraic"].as_u64().unwrap() * width
+            })
+            .sum();
+        assert_eq!(accounted, 5);
+        assert_eq!(groups.len(), 3);
+        // The merged clusters have no clean rank drop, so no form is built.
+        assert!(out["form"].is_null());
+    }
+
+    #[test]
+    fn scientific_notation_strings_read_as_numbers() {
+        assert_eq!(parse_numeric_string("1.5e-3"), Some(1.5e-3));
+        assert_eq!(parse_numeric_string(" -2E2 "), Some(-200.0));
+        for bad in ["abc", "1e400", "NaN"] {
+            assert_eq!(parse_numeric_string(bad), None, "{}", bad);
+        }
+        for bad in [
+            serde_json::json!("1.5e-3"),
+            serde_json::json!(true),
+            Value::Null,
+            serde_json::json!([1.0]),
+        ] {
+            assert_eq!(json_number_to_f64(&bad), None, "{}", bad);
+        }
+        let rows = serde_json::json!([[2.0, "1e-1"], ["0.1", "3"]]);
+        let strict = serde_json::json!({ "rows": rows, "symmetric": true });
+        assert!(invoke_var("pod.eigs/eigenvalues", &strict).is_err());
+        let out = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "symmetric": true, "allow_string_entries": true }),
+        )
+        .unwrap();
+        let values: Vec<f64> = serde_json::from_value(out["eigenvalues"].clone()).unwrap();
+        assert!(
+            close_enough(values[0] + values[1], 5.0) && close_enough(values[0] * values[1], 5.99)
+        );
+        let rows = serde_json::json!([[2.0, "one"], ["0.1", "3"]]);
+        let arg = serde_json::json!({ "rows": rows, "allow_string_entries": true });
+        assert!(invoke_var("pod.eigs/eigenvalues", &arg).is_err());
+    }
+
+    #[test]
+    fn matrix_power_by_squaring() {
+        let power = |rows: &Value, k: i64| {
+            invoke_var(
+                "pod.eigs/power",
+                &serde_json::json!({ "rows": rows, "exponent": k }),
+            )
+            .map(|out| out["power"].clone())
+        };
+        let a = serde_json::json!([[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(power(&a, 1).unwrap(), a);
+        assert_eq!(
+            power(&a, 0).unwrap(),
+            serde_json::json!([[1.0, 0.0], [0.0, 1.0]])
+        );
+        assert_eq!(
+            power(&a, 3).unwrap(),
+            serde_json::json!([[37.0, 54.0], [81.0, 118.0]])
+        );
+
+        let identity: Vec<Vec<f64>> = (0..6)
+            .map(|i| (0..6).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        let identity = serde_json::json!(identity);
+        assert_eq!(power(&identity, 13).unwrap(), identity);
+
+        // The shear block [[1 1] [0 1]]^k = [[1 k] [0 1]], embedded in the 6×6 identity.
+        let mut shear = identity.clone();
+        shear[2][3] = serde_json::json!(1.0);
+        let out = power(&shear, 10).unwrap();
+        assert_eq!(out[2][3], 10.0);
+        assert_eq!(out[2][2], 1.0);
+        let out = power(&shear, -4).unwrap();
+        assert_eq!(out[2][3], -4.0);
+
+        assert!(power(&serde_json::json!([[1.0, 2.0], [2.0, 4.0]]), -1).is_err());
+        assert!(invoke_var(
+            "pod.eigs/power",
+            &serde_json::json!({ "rows": a, "exponent": 1.5 })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn spectral_radius_uses_modulus() {
+        // Eigenvalues 1 ± 2i (modulus √5) and -2.
+        let rows = serde_json::json!([[1.0, -2.0, 0.0], [2.0, 1.0, 0.0], [0.0, 0.0, -2.0]]);
+        let out = invoke_var(
+            "pod.eigs/spectral-radius",
+            &serde_json::json!({ "rows": rows }),
+        )
+        .unwrap();
+        assert!(close_enough(
+            out["spectral_radius"].as_f64().unwrap(),
+            5.0f64.sqrt()
+        ));
+
+        let rows = serde_json::json!([[-3.0, 1.0], [1.0, -3.0]]);
+        let out = invoke_var(
+            "pod.eigs/spectral-radius",
+            &serde_json::json!({ "rows": rows, "symmetric": true }),
+        )
+        .unwrap();
+        assert!(close_enough(out["spectral_radius"].as_f64().unwrap(), 4.0));
+        for n in [2, 3] {
+            let zero = serde_json::json!({ "rows": vec![vec![0.0; n]; n] });
+            assert_eq!(
+                invoke_var("pod.eigs/spectral-radius", &zero).unwrap()["spectral_radius"],
+                0.0
+            );
+        }
+    }
+
+    #[test]
+    fn sym_tol_relaxes_the_symmetry_check() {
+        let rows = serde_json::json!([[2.0, 1.0 + 2.0e-6, 0.0], [1.0, 3.0, 0.0], [0.0, 0.0, 1.0]]);
+        let err = invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "symmetric": true }),
+        )
+        .unwrap_err();
+        assert!(err.contains("a[0][1] - a[1][0]"), "{}", err);
+        assert!(err.contains("2.0000"), "{}", err);
+        let relaxed = serde_json::json!({ "rows": rows, "symmetric": true, "sym_tol": 1.0e-5 });
+        let out = invoke_var("pod.eigs/eigenvalues", &relaxed).unwrap();
+        assert_eq!(out["eigenvalues"].as_array().unwrap().len(), 3);
+        assert!(invoke_var("pod.eigs/spectral-radius", &relaxed).is_ok());
+        assert!(invoke_var(
+            "pod.eigs/eigenvalues",
+            &serde_json::json!({ "rows": rows, "symmetric": true, "sym_tol": -1.0 })
+        )
+        .is_err());
+
+        let b = serde_json::json!([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert!(invoke_var(
+            "pod.eigs/spd-geometric-mean",
+            &serde_json::json!({ "a": rows, "b": b })
+        )
+        .is_err());
+        let out = invoke_var(
+            "pod.eigs/spd-geometric-mean",
+            &serde_json::json!({ "a": rows, "b": b, "sym_tol": 1.0e-5 }),
+        );
+        assert!(out.is_ok(), "{:?}", out);
+
+        let skew = serde_json::json!([[0.0, 2.0 + 2.0e-6], [-2.0, 0.0]]);
+        assert!(invoke_var("pod.eigs/pfaffian", &serde_json::json!({ "rows": skew })).is_err());
+        let out = invoke_var(
+            "pod.eigs/pfaffian",
+            &serde_json::json!({ "rows": skew, "sym_tol": 1.0e-5 }),
+        )
+        .unwrap();
+        assert!((out["pfaffian"].as_f64().unwrap() - 2.0).abs() < 1.0e-5);
     }
 }